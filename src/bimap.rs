@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+
+use super::map::{Iter, TSTMap};
+
+/// One-to-one map between string keys and values on top of `TSTMap`, with a `BTreeMap`
+/// for lookups by value. Both directions are kept consistent on every change.
+#[derive(Clone)]
+pub struct TSTBiMap<Value: Ord + Clone> {
+    by_key: TSTMap<Value>,
+    by_value: BTreeMap<Value, String>,
+}
+
+impl<Value: Ord + Clone> Default for TSTBiMap<Value> {
+    fn default() -> Self {
+        TSTBiMap {
+            by_key: TSTMap::new(),
+            by_value: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Value: Ord + Clone> TSTBiMap<Value> {
+    /// Constructs a new, empty `TSTBiMap<Value>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBiMap;
+    /// let mut m: TSTBiMap<u32> = TSTBiMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the number of pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBiMap;
+    ///
+    /// let mut m = TSTBiMap::new();
+    /// m.insert("alice", 1);
+    /// m.insert("bob", 2);
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.by_key.len()
+    }
+
+    /// Returns true if there are no pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBiMap;
+    ///
+    /// let mut m = TSTBiMap::new();
+    /// assert!(m.is_empty());
+    /// m.insert("alice", 1);
+    /// assert!(!m.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.by_key.is_empty()
+    }
+
+    /// Returns the value paired with `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBiMap;
+    ///
+    /// let mut m = TSTBiMap::new();
+    /// m.insert("alice", 1);
+    /// assert_eq!(Some(&1), m.get_by_key("alice"));
+    /// assert_eq!(None, m.get_by_key("bob"));
+    /// ```
+    pub fn get_by_key(&self, key: &str) -> Option<&Value> {
+        self.by_key.get(key)
+    }
+
+    /// Returns the key paired with `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBiMap;
+    ///
+    /// let mut m = TSTBiMap::new();
+    /// m.insert("alice", 1);
+    /// assert_eq!(Some("alice"), m.get_by_value(&1));
+    /// assert_eq!(None, m.get_by_value(&2));
+    /// ```
+    pub fn get_by_value(&self, value: &Value) -> Option<&str> {
+        self.by_value.get(value).map(String::as_str)
+    }
+
+    /// Inserts the pair of `key` and `value`, removing the pairs either of them was in.
+    /// Returns the old value of `key` and the old key of `value`, if there were ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBiMap;
+    ///
+    /// let mut m = TSTBiMap::new();
+    /// assert_eq!((None, None), m.insert("alice", 1));
+    /// assert_eq!((None, None), m.insert("bob", 2));
+    /// assert_eq!((Some(1), Some("bob".to_string())), m.insert("alice", 2));
+    /// assert_eq!(1, m.len());
+    /// assert_eq!(None, m.get_by_value(&1));
+    /// ```
+    pub fn insert(&mut self, key: &str, value: Value) -> (Option<Value>, Option<String>) {
+        assert!(!key.is_empty(), "Empty key");
+        let old_value = self.remove_by_key(key);
+        let old_key = self.remove_by_value(&value);
+        self.by_value.insert(value.clone(), key.to_string());
+        self.by_key.insert(key, value);
+        (old_value, old_key)
+    }
+
+    /// Inserts the pair of `key` and `value`, only if neither of them is paired yet.
+    /// Otherwise returns them back and leaves the `TSTBiMap` unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBiMap;
+    ///
+    /// let mut m = TSTBiMap::new();
+    /// assert_eq!(Ok(()), m.insert_no_overwrite("alice", 1));
+    /// assert_eq!(Err(("bob".to_string(), 1)), m.insert_no_overwrite("bob", 1));
+    /// assert_eq!(Err(("alice".to_string(), 2)), m.insert_no_overwrite("alice", 2));
+    /// assert_eq!(1, m.len());
+    /// ```
+    pub fn insert_no_overwrite(&mut self, key: &str, value: Value) -> Result<(), (String, Value)> {
+        assert!(!key.is_empty(), "Empty key");
+        if self.by_key.contains_key(key) || self.by_value.contains_key(&value) {
+            return Err((key.to_string(), value));
+        }
+        self.by_value.insert(value.clone(), key.to_string());
+        self.by_key.insert(key, value);
+        Ok(())
+    }
+
+    /// Removes the pair of `key`, returning its value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBiMap;
+    ///
+    /// let mut m = TSTBiMap::new();
+    /// m.insert("alice", 1);
+    /// assert_eq!(Some(1), m.remove_by_key("alice"));
+    /// assert_eq!(None, m.get_by_value(&1));
+    /// assert_eq!(None, m.remove_by_key("alice"));
+    /// ```
+    pub fn remove_by_key(&mut self, key: &str) -> Option<Value> {
+        let value = self.by_key.remove(key)?;
+        self.by_value.remove(&value);
+        Some(value)
+    }
+
+    /// Removes the pair of `value`, returning its key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBiMap;
+    ///
+    /// let mut m = TSTBiMap::new();
+    /// m.insert("alice", 1);
+    /// assert_eq!(Some("alice".to_string()), m.remove_by_value(&1));
+    /// assert_eq!(None, m.get_by_key("alice"));
+    /// assert_eq!(None, m.remove_by_value(&1));
+    /// ```
+    pub fn remove_by_value(&mut self, value: &Value) -> Option<String> {
+        let key = self.by_value.remove(value)?;
+        self.by_key.remove(&key);
+        Some(key)
+    }
+
+    /// Gets an iterator over the pairs in the order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTBiMap;
+    ///
+    /// let mut m = TSTBiMap::new();
+    /// m.insert("bob", 1);
+    /// m.insert("alice", 2);
+    /// let pairs: Vec<_> = m.iter().collect();
+    /// assert_eq!(vec![("alice".to_string(), &2), ("bob".to_string(), &1)], pairs);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, Value> {
+        self.by_key.iter()
+    }
+}
@@ -0,0 +1,309 @@
+//! Compact binary format of `TSTMap`.
+//!
+//! The format starts with the `TST\0` magic and a version byte, followed by the number
+//! of values and all nodes of the tree in pre-order. Every node is written as a byte of
+//! flags, its char in UTF-8 and its value, if it has one. Values are encoded by a
+//! `ValueCodec`, which is `BinaryCodec` for `TSTMap::write_to` and `TSTMap::read_from`.
+
+use std::io::{self, Read, Write};
+
+use bumpalo_herd::Herd;
+
+use super::node::{BoxedNode, BoxedNodeRefMut, NodeRef};
+
+const MAGIC: &[u8; 4] = b"TST\0";
+const VERSION: u8 = 1;
+
+const HAS_LT: u8 = 1;
+const HAS_EQ: u8 = 1 << 1;
+const HAS_GT: u8 = 1 << 2;
+const HAS_VALUE: u8 = 1 << 3;
+
+/// Values, which could be written to and read from the binary format of `TSTMap`.
+/// Numbers are stored in little-endian, `usize` and `isize` in 64 bits, strings and byte
+/// vectors with their length ahead.
+pub trait BinaryValue: Sized {
+    /// Writes the value to `w`.
+    fn write_value<W: Write>(&self, w: &mut W) -> io::Result<()>;
+    /// Reads a value written by `write_value` from `r`.
+    fn read_value<R: Read>(r: &mut R) -> io::Result<Self>;
+}
+
+/// Encoding of values in the binary format of `TSTMap`, for `TSTMap::save` and
+/// `TSTMap::load`. Keys and the structure of the tree are written by the crate itself.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{self, Read, Write};
+/// use tst::{TSTMap, ValueCodec};
+///
+/// // stores chars in a single byte
+/// struct Ascii;
+///
+/// impl ValueCodec<char> for Ascii {
+///     fn encode<W: Write>(&self, value: &char, w: &mut W) -> io::Result<()> {
+///         w.write_all(&[*value as u8])
+///     }
+///     fn decode<R: Read>(&self, r: &mut R) -> io::Result<char> {
+///         let mut buf = [0];
+///         r.read_exact(&mut buf)?;
+///         Ok(buf[0] as char)
+///     }
+/// }
+///
+/// let mut m = TSTMap::new();
+/// m.insert("abc", 'x');
+/// let mut blob = vec![];
+/// m.save(&mut blob, &Ascii).unwrap();
+/// assert_eq!(m, TSTMap::load(&mut &blob[..], &Ascii).unwrap());
+/// ```
+pub trait ValueCodec<Value> {
+    /// Writes `value` to `w`.
+    fn encode<W: Write>(&self, value: &Value, w: &mut W) -> io::Result<()>;
+    /// Reads a value written by `encode` from `r`.
+    fn decode<R: Read>(&self, r: &mut R) -> io::Result<Value>;
+}
+
+/// `ValueCodec` of values implementing `BinaryValue`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BinaryCodec;
+
+impl<Value: BinaryValue> ValueCodec<Value> for BinaryCodec {
+    fn encode<W: Write>(&self, value: &Value, w: &mut W) -> io::Result<()> {
+        value.write_value(w)
+    }
+    fn decode<R: Read>(&self, r: &mut R) -> io::Result<Value> {
+        Value::read_value(r)
+    }
+}
+
+macro_rules! impl_binary_value_for_num {
+    ($($t:ty),*) => {
+        $(
+            impl BinaryValue for $t {
+                fn write_value<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                    w.write_all(&self.to_le_bytes())
+                }
+                fn read_value<R: Read>(r: &mut R) -> io::Result<Self> {
+                    let mut buf = [0; std::mem::size_of::<$t>()];
+                    r.read_exact(&mut buf)?;
+                    Ok(<$t>::from_le_bytes(buf))
+                }
+            }
+        )*
+    };
+}
+
+impl_binary_value_for_num!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+// sizes are stored in 64 bits to be read back on platforms of any width
+macro_rules! impl_binary_value_for_size {
+    ($($t:ty => $wide:ty),*) => {
+        $(
+            impl BinaryValue for $t {
+                fn write_value<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                    (*self as $wide).write_value(w)
+                }
+                fn read_value<R: Read>(r: &mut R) -> io::Result<Self> {
+                    <$t>::try_from(<$wide>::read_value(r)?)
+                        .map_err(|_| invalid_data("value is out of range of the platform"))
+                }
+            }
+        )*
+    };
+}
+
+impl_binary_value_for_size!(usize => u64, isize => i64);
+
+impl BinaryValue for () {
+    fn write_value<W: Write>(&self, _: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+    fn read_value<R: Read>(_: &mut R) -> io::Result<Self> {
+        Ok(())
+    }
+}
+
+impl BinaryValue for bool {
+    fn write_value<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u8).write_value(w)
+    }
+    fn read_value<R: Read>(r: &mut R) -> io::Result<Self> {
+        match u8::read_value(r)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid_data("invalid bool value")),
+        }
+    }
+}
+
+impl BinaryValue for Vec<u8> {
+    fn write_value<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (self.len() as u64).write_value(w)?;
+        w.write_all(self)
+    }
+    fn read_value<R: Read>(r: &mut R) -> io::Result<Self> {
+        let len = u64::read_value(r)?;
+        let mut buf = vec![];
+        r.take(len).read_to_end(&mut buf)?;
+        if buf.len() as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(buf)
+    }
+}
+
+impl BinaryValue for String {
+    fn write_value<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (self.len() as u64).write_value(w)?;
+        w.write_all(self.as_bytes())
+    }
+    fn read_value<R: Read>(r: &mut R) -> io::Result<Self> {
+        String::from_utf8(Vec::<u8>::read_value(r)?)
+            .map_err(|_| invalid_data("invalid UTF-8 in string value"))
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_char<W: Write>(c: char, w: &mut W) -> io::Result<()> {
+    let mut buf = [0; 4];
+    w.write_all(c.encode_utf8(&mut buf).as_bytes())
+}
+
+fn read_char<R: Read>(r: &mut R) -> io::Result<char> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf[..1])?;
+    let len = match buf[0] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return Err(invalid_data("invalid UTF-8 in key")),
+    };
+    r.read_exact(&mut buf[1..len])?;
+    std::str::from_utf8(&buf[..len])
+        .ok()
+        .and_then(|s| s.chars().next())
+        .ok_or_else(|| invalid_data("invalid UTF-8 in key"))
+}
+
+// writes header and all nodes of the tree
+pub fn write_tree<Value, W, F>(
+    node: NodeRef<Value>,
+    size: usize,
+    w: &mut W,
+    mut write_value: F,
+) -> io::Result<()>
+where
+    W: Write,
+    F: FnMut(&Value, &mut W) -> io::Result<()>,
+{
+    w.write_all(MAGIC)?;
+    VERSION.write_value(w)?;
+    (size as u64).write_value(w)?;
+    node.as_option().is_some().write_value(w)?;
+
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        let cur = match node.as_option() {
+            Some(cur) => cur,
+            None => continue,
+        };
+        let mut flags = 0;
+        if cur.lt.is_some() {
+            flags |= HAS_LT;
+        }
+        if cur.eq.is_some() {
+            flags |= HAS_EQ;
+        }
+        if cur.gt.is_some() {
+            flags |= HAS_GT;
+        }
+        if cur.value.is_some() {
+            flags |= HAS_VALUE;
+        }
+        flags.write_value(w)?;
+        write_char(cur.c, w)?;
+        if let Some(ref value) = cur.value {
+            write_value(value, w)?;
+        }
+        stack.push(cur.gt.as_ref());
+        stack.push(cur.eq.as_ref());
+        stack.push(cur.lt.as_ref());
+    }
+    Ok(())
+}
+
+// reads nodes written by `write_tree` into the empty `node`, returns number of values
+pub fn read_tree<Value, R, F>(
+    node: BoxedNodeRefMut<Value>,
+    pool: &mut Herd,
+    r: &mut R,
+    mut read_value: F,
+) -> io::Result<usize>
+where
+    R: Read,
+    F: FnMut(&mut R) -> io::Result<Value>,
+{
+    let mut magic = [0; 4];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a TSTMap binary"));
+    }
+    if u8::read_value(r)? != VERSION {
+        return Err(invalid_data("unsupported TSTMap binary version"));
+    }
+    let size = u64::read_value(r)?;
+
+    let mut count = 0;
+    // every node comes with the bounds of its char, which lt and gt children narrow
+    let mut stack = vec![];
+    // nodes in the order of reading, so children go after their parents
+    let mut read = vec![];
+    if bool::read_value(r)? {
+        stack.push((node, None, None));
+    }
+    while let Some((mut node, lo, hi)) = stack.pop() {
+        let flags = u8::read_value(r)?;
+        if flags & !(HAS_LT | HAS_EQ | HAS_GT | HAS_VALUE) != 0 {
+            return Err(invalid_data("invalid node flags"));
+        }
+        let c = read_char(r)?;
+        if lo.is_some_and(|lo| c <= lo) || hi.is_some_and(|hi| c >= hi) {
+            return Err(invalid_data("chars of nodes are out of order"));
+        }
+        node.assign(BoxedNode::new(c, pool));
+        read.push(node.clone());
+        let cur = node.as_node_ref();
+        if flags & HAS_VALUE != 0 {
+            cur.value = Some(read_value(r)?);
+            // the order of inserts is not stored, so take the order of reading
+            #[cfg(feature = "insertion-order")]
+            {
+                cur.seq = count;
+            }
+            count += 1;
+        }
+        if flags & HAS_GT != 0 {
+            stack.push((cur.gt.as_mut(), Some(c), hi));
+        }
+        if flags & HAS_EQ != 0 {
+            stack.push((cur.eq.as_mut(), None, None));
+        }
+        if flags & HAS_LT != 0 {
+            stack.push((cur.lt.as_mut(), lo, Some(c)));
+        }
+    }
+    if count as u64 != size {
+        return Err(invalid_data("number of values doesn't match the header"));
+    }
+    for node in read.iter().rev() {
+        let cur = node.as_node_ref();
+        cur.size = cur.value.is_some() as usize + cur.lt.size() + cur.eq.size() + cur.gt.size();
+    }
+    Ok(count)
+}
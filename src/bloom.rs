@@ -0,0 +1,73 @@
+//! Bloom filter of keys, which lets `TSTMap::with_bloom` reject most of missing keys
+//! without descending the tree.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+
+// bits per expected key and number of hashes for about 1% of false positives
+const BITS_PER_KEY: usize = 10;
+const HASHES: u64 = 7;
+// positions are made of 32-bit halves of a hash, which tell apart no more bits
+const MAX_BITS: usize = u32::MAX as usize;
+
+#[derive(Clone, Debug)]
+pub struct Bloom {
+    bits: Vec<u64>,
+    // number of keys the filter is sized for
+    expected: usize,
+    // number of keys inserted since the filter was empty
+    inserted: usize,
+}
+
+impl Bloom {
+    pub fn new(expected: usize) -> Self {
+        let expected = expected.max(1);
+        let words = expected
+            .saturating_mul(BITS_PER_KEY)
+            .min(MAX_BITS)
+            .div_ceil(64);
+        Bloom {
+            bits: vec![0; words],
+            expected,
+            inserted: 0,
+        }
+    }
+
+    // positions of the bits of `key`, made of two halves of one hash
+    fn positions(&self, key: &str) -> impl Iterator<Item = usize> {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(key.as_bytes());
+        let hash = hasher.finish();
+        let (h1, h2) = (hash & 0xffff_ffff, hash >> 32);
+        let len = self.bits.len() as u64 * 64;
+        (0..HASHES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    pub fn insert(&mut self, key: &str) {
+        for pos in self.positions(key) {
+            self.bits[pos / 64] |= 1 << (pos % 64);
+        }
+        self.inserted += 1;
+    }
+
+    // false means the key was never inserted, true means it might have been
+    pub fn contains(&self, key: &str) -> bool {
+        self.positions(key)
+            .all(|pos| self.bits[pos / 64] & (1 << (pos % 64)) != 0)
+    }
+
+    // an empty filter of the same size
+    pub fn cleared(&self) -> Self {
+        Bloom {
+            bits: vec![0; self.bits.len()],
+            expected: self.expected,
+            inserted: 0,
+        }
+    }
+
+    // true if removed keys, which are the inserted ones besides `kept` ones,
+    // make up more than a quarter of the expected keys
+    pub fn is_stale(&self, kept: usize) -> bool {
+        self.inserted.saturating_sub(kept) > self.expected / 4
+    }
+}
@@ -0,0 +1,178 @@
+use super::map::{Iter, KeysIter, TSTMap, ValuesIter, WildCardIter};
+
+/// Read-only `TSTMap`, returned by `TSTMap::freeze`.
+///
+/// It has no methods to change it, so it could be shared between threads
+/// (for example in an `Arc`) without locks, when the values are `Sync`.
+pub struct FrozenTST<Value> {
+    map: TSTMap<Value>,
+}
+
+impl<Value> FrozenTST<Value> {
+    pub(crate) fn new(mut map: TSTMap<Value>) -> Self {
+        map.optimize_layout();
+        FrozenTST { map }
+    }
+
+    /// Returns the number of elements in the container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert_eq!(1, m.freeze().len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the container holds no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let m = TSTMap::<i32>::new();
+    /// assert!(m.freeze().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns a reference to the value corresponding to the `key` or None.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// let f = m.freeze();
+    /// assert_eq!(Some(&1), f.get("abc"));
+    /// assert_eq!(None, f.get("abd"));
+    /// ```
+    pub fn get<Key: AsRef<str>>(&self, key: Key) -> Option<&Value> {
+        self.map.get(key)
+    }
+
+    /// Returns true if the container has a value for the `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert!(m.freeze().contains_key("abc"));
+    /// ```
+    pub fn contains_key<Key: AsRef<str>>(&self, key: Key) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Method returns longest prefix `pref` in the container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert_eq!("abc", m.freeze().longest_prefix("abcd"));
+    /// ```
+    pub fn longest_prefix<'x>(&self, pref: &'x str) -> &'x str
+    where
+        Value: 'x,
+    {
+        self.map.longest_prefix(pref)
+    }
+
+    /// Gets an iterator over the entries of the container in sorted order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    /// let f = m.freeze();
+    /// assert_eq!(vec![("a".to_string(), &1), ("b".to_string(), &2)], f.iter().collect::<Vec<_>>());
+    /// ```
+    pub fn iter(&self) -> Iter<'_, Value> {
+        self.map.iter()
+    }
+
+    /// Method returns iterator over all values with common prefix `pref` in the container.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    /// m.insert("b", 3);
+    /// assert_eq!(2, m.freeze().prefix_iter("ab").count());
+    /// ```
+    pub fn prefix_iter(&self, pref: &str) -> Iter<'_, Value> {
+        self.map.prefix_iter(pref)
+    }
+
+    /// An iterator returning all entries matching wildcard pattern `pat`,
+    /// see `TSTMap::wildcard_iter`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    /// assert_eq!(2, m.freeze().wildcard_iter("ab.").count());
+    /// ```
+    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<'_, Value> {
+        self.map.wildcard_iter(pat)
+    }
+
+    /// An iterator visiting all keys in sorted order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    /// assert_eq!(vec!["a", "b"], m.freeze().keys().collect::<Vec<_>>());
+    /// ```
+    pub fn keys(&self) -> KeysIter<'_, Value> {
+        self.map.keys()
+    }
+
+    /// An iterator visiting all values in order of their keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    /// assert_eq!(vec![&1, &2], m.freeze().values().collect::<Vec<_>>());
+    /// ```
+    pub fn values(&self) -> ValuesIter<'_, Value> {
+        self.map.values()
+    }
+}
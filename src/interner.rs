@@ -0,0 +1,127 @@
+use super::map::Entry::*;
+use super::map::TSTMap;
+
+/// String interner on top of `TSTMap`, which gives every distinct string
+/// a stable dense id starting from 0.
+#[derive(Clone, Default)]
+pub struct Interner {
+    // ids of interned strings, kept in the terminal nodes
+    ids: TSTMap<u32>,
+    // id of the empty string, it can't be a key of the map
+    empty: Option<u32>,
+    strings: Vec<String>,
+}
+
+impl Interner {
+    /// Constructs a new, empty `Interner`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::Interner;
+    /// let mut i = Interner::new();
+    /// ```
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the number of interned strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::Interner;
+    ///
+    /// let mut i = Interner::new();
+    /// i.intern("foo");
+    /// i.intern("foo");
+    /// assert_eq!(1, i.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns true if nothing was interned yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::Interner;
+    ///
+    /// let mut i = Interner::new();
+    /// assert!(i.is_empty());
+    /// i.intern("foo");
+    /// assert!(!i.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Returns the id of `s`, assigning the next free one if `s` was not interned before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `u32::MAX` strings are interned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::Interner;
+    ///
+    /// let mut i = Interner::new();
+    /// assert_eq!(0, i.intern("foo"));
+    /// assert_eq!(1, i.intern("bar"));
+    /// assert_eq!(0, i.intern("foo"));
+    /// ```
+    pub fn intern(&mut self, s: &str) -> u32 {
+        let next = u32::try_from(self.strings.len()).expect("Too many interned strings");
+        let id = if s.is_empty() {
+            *self.empty.get_or_insert(next)
+        } else {
+            match self.ids.entry(s) {
+                Occupied(entry) => *entry.get(),
+                Vacant(entry) => *entry.insert(next),
+            }
+        };
+        if id == next {
+            self.strings.push(s.to_string());
+        }
+        id
+    }
+
+    /// Returns the id of `s`, if it was interned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::Interner;
+    ///
+    /// let mut i = Interner::new();
+    /// i.intern("foo");
+    /// assert_eq!(Some(0), i.get("foo"));
+    /// assert_eq!(None, i.get("bar"));
+    /// ```
+    pub fn get(&self, s: &str) -> Option<u32> {
+        if s.is_empty() {
+            self.empty
+        } else {
+            self.ids.get(s).copied()
+        }
+    }
+
+    /// Returns the string with the given `id`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::Interner;
+    ///
+    /// let mut i = Interner::new();
+    /// let id = i.intern("foo");
+    /// assert_eq!(Some("foo"), i.resolve(id));
+    /// assert_eq!(None, i.resolve(id + 1));
+    /// ```
+    pub fn resolve(&self, id: u32) -> Option<&str> {
+        self.strings.get(id as usize).map(String::as_str)
+    }
+}
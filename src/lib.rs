@@ -36,9 +36,20 @@ macro_rules! tstmap {
 
 extern crate core;
 
+pub mod bimap;
+pub mod binary;
+pub mod bloom;
+pub mod frozen;
+pub mod interner;
 pub mod map;
 /// TST container map and set implementation.
 pub mod node;
 pub mod traverse;
 
-pub use map::TSTMap;
+pub use bimap::TSTBiMap;
+pub use binary::{BinaryCodec, BinaryValue, ValueCodec};
+pub use frozen::FrozenTST;
+pub use interner::Interner;
+#[cfg(feature = "unicode-normalization")]
+pub use map::Normalization;
+pub use map::{merge_sorted, TSTMap};
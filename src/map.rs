@@ -1,15 +1,37 @@
 use bumpalo_herd::Herd;
 
 use self::Entry::*;
-use super::node::{BoxedNode, Node, NodeRef, NodeRefMut};
+use super::binary::{self, BinaryCodec, BinaryValue, ValueCodec};
+use super::bloom::Bloom;
+use super::frozen::FrozenTST;
+use super::node::{BoxedNode, BoxedNodeRefMut, Node, NodeRef, NodeRefMut};
 use super::traverse::{
-    self, DropTraverse, IntoTraverse, Traverse, ValuesTraverse, WildCardTraverse,
+    self, CharCmp, DamerauTraverse, DropTraverse, FuzzyPrefixTraverse, IntoTraverse, KeyPath,
+    SuffixPattern, Traverse, ValuesTraverse, WildCardTraverse,
 };
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+#[cfg(feature = "insertion-order")]
+use std::collections::VecDeque;
 use std::default::Default;
+use std::error::Error;
 use std::fmt::{self, Debug};
+use std::io::{self, BufRead, Read, Write};
 use std::iter::{FromIterator, Map};
+use std::marker::PhantomData;
 use std::mem;
-use std::ops;
+use std::ops::{self, ControlFlow, Range};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "rayon")]
+use rayon::iter::{plumbing::UnindexedConsumer, IntoParallelIterator, ParallelIterator};
+#[cfg(feature = "regex")]
+use regex::Regex;
+#[cfg(feature = "unicode-normalization")]
+use unicode_normalization::{
+    is_nfc_quick, is_nfd_quick, is_nfkc_quick, is_nfkd_quick, IsNormalized, UnicodeNormalization,
+};
 
 ///
 /// Symbol table with string keys, implemented using a ternary search
@@ -58,6 +80,233 @@ pub struct TSTMap<Value> {
     pub pool: Herd,
     pub root: BoxedNode<Value>,
     pub size: usize,
+    // sequence number of the next insert
+    #[cfg(feature = "insertion-order")]
+    next_seq: usize,
+    // form keys are stored in and order of their chars
+    form: KeyForm,
+    // filter of inserted keys to answer `contains_key` for most missing ones
+    bloom: Option<Bloom>,
+    // number of entries inserts evict other ones beyond
+    max_entries: Option<usize>,
+    // keys of a map with `max_entries` by sequence numbers of their inserts, oldest first,
+    // including ones removed since, which are skipped on eviction
+    #[cfg(feature = "insertion-order")]
+    inserts: VecDeque<(usize, String)>,
+}
+
+// an entry evicted to make room for a new key
+type Evicted<Value> = Option<(String, Value)>;
+
+// form keys of a TSTMap are stored in and order of their chars, which every key
+// taken by the TSTMap, its iterators and entries is brought to
+#[derive(Clone, Copy, Default)]
+struct KeyForm {
+    // form all keys are brought to
+    #[cfg(feature = "unicode-normalization")]
+    normalization: Option<Normalization>,
+    // order of chars in nodes, code point order if none
+    char_order: Option<CharCmp>,
+    // whether keys are stored with their chars reversed
+    reversed: bool,
+}
+
+impl KeyForm {
+    // order of chars in nodes
+    fn cmp(&self) -> CharCmp {
+        self.char_order.unwrap_or(traverse::code_point_order)
+    }
+
+    // `key` in the form it's stored in, normalized before reversing,
+    // as normalization of reversed chars would reorder combining marks
+    fn apply<'k>(&self, key: &'k str) -> Cow<'k, str> {
+        self.orient(self.normalize(key))
+    }
+
+    // `key` brought to the normalization form
+    fn normalize<'k>(&self, key: &'k str) -> Cow<'k, str> {
+        #[cfg(feature = "unicode-normalization")]
+        if let Some(form) = self.normalization {
+            return form.apply(key);
+        }
+        Cow::Borrowed(key)
+    }
+
+    // `key` in the orientation it's stored in, which also turns a stored key back
+    fn orient<'k>(&self, key: Cow<'k, str>) -> Cow<'k, str> {
+        if self.reversed {
+            Cow::Owned(key.chars().rev().collect())
+        } else {
+            key
+        }
+    }
+
+    // entry with a stored key turned back to the orientation it was given in
+    fn restore<V>(&self, (key, value): (String, V)) -> (String, V) {
+        (self.orient(key.into()).into_owned(), value)
+    }
+
+    // whether keys are stored as they are given
+    fn is_identity(&self) -> bool {
+        #[cfg(feature = "unicode-normalization")]
+        if self.normalization.is_some() {
+            return false;
+        }
+        !self.reversed
+    }
+
+    // `key` brought to the normalization form, if it differs from the given one
+    fn changes(&self, key: &str) -> Option<String> {
+        match self.normalize(key) {
+            Cow::Owned(stored) if stored != key => Some(stored),
+            _ => None,
+        }
+    }
+}
+
+// nodes and counters of a TSTMap borrowed apart, which entries and cursors insert with
+struct Parts<'x, Value: 'x> {
+    root: BoxedNodeRefMut<'x, Value>,
+    pool: &'x mut Herd,
+    size: &'x mut usize,
+    #[cfg(feature = "insertion-order")]
+    next_seq: &'x mut usize,
+    bloom: Option<&'x mut Bloom>,
+    form: KeyForm,
+    max_entries: Option<usize>,
+    #[cfg(feature = "insertion-order")]
+    inserts: &'x mut VecDeque<(usize, String)>,
+}
+
+impl<'x, Value> Parts<'x, Value> {
+    // node of the stored `key`, created if it's missing, the path to it
+    // and the entry evicted to make room for the key
+    fn locate(&mut self, key: &str) -> (KeyPath<'x, Value>, &'x mut Node<Value>, Evicted<Value>) {
+        let evicted = self.prepare(key);
+        let (path, node) = traverse::insert(self.root.clone(), key, self.pool, self.form.cmp());
+        (path, node, evicted)
+    }
+
+    // upkeep before inserting the stored `key`, returns the entry evicted to make room for it
+    fn prepare(&mut self, key: &str) -> Evicted<Value> {
+        self.refresh_bloom();
+        let max = self.max_entries?;
+        #[cfg(feature = "insertion-order")]
+        if self.inserts.len() > 2 * max {
+            *self.inserts = traverse::keys_by_seq(self.root.as_mut().as_ref()).into();
+        }
+        let cmp = self.form.cmp();
+        if *self.size < max
+            || traverse::search(self.root.as_mut().as_ref(), key, cmp)
+                .is_some_and(|node| node.value.is_some())
+        {
+            return None;
+        }
+        let mut evicted = None;
+        while *self.size >= max {
+            evicted = Some(self.evict()?);
+        }
+        evicted
+    }
+
+    // removes the entry to evict, the oldest one or the one with the smallest key
+    fn evict(&mut self) -> Evicted<Value> {
+        let cmp = self.form.cmp();
+        #[cfg(feature = "insertion-order")]
+        let key = loop {
+            let (seq, key) = self.inserts.pop_front()?;
+            // a key removed or inserted again since has no value or a later one
+            let node = traverse::search(self.root.as_mut().as_ref(), &key, cmp);
+            if node.is_some_and(|node| node.value.is_some() && node.seq == seq) {
+                break key;
+            }
+        };
+        #[cfg(not(feature = "insertion-order"))]
+        let key = traverse::first_entry(self.root.as_mut().as_ref(), false)?.0;
+        let (value, _) = traverse::remove(self.root.clone(), &key, cmp)?;
+        *self.size -= 1;
+        Some(self.form.restore((key, value)))
+    }
+
+    // value of the stored `key`, inserting the result of `f` first if it's missing
+    fn get_or_insert_with<F: FnOnce() -> Value>(&mut self, key: &str, f: F) -> &'x mut Value {
+        let (path, node, _) = self.locate(key);
+        match node.value {
+            Some(ref mut value) => value,
+            None => self.put(path, node, key, f()),
+        }
+    }
+
+    // puts the value of a new stored `key` into its empty `node` at the end of `path`
+    fn put<'n>(
+        &mut self,
+        path: KeyPath<'_, Value>,
+        node: &'n mut Node<Value>,
+        key: &str,
+        value: Value,
+    ) -> &'n mut Value {
+        path.grow();
+        *self.size += 1;
+        #[cfg(feature = "insertion-order")]
+        {
+            node.seq = *self.next_seq;
+            *self.next_seq += 1;
+            if self.max_entries.is_some() {
+                self.inserts.push_back((node.seq, key.to_string()));
+            }
+        }
+        if let Some(ref mut bloom) = self.bloom {
+            bloom.insert(key);
+        }
+        node.value.insert(value)
+    }
+
+    // rebuilds the Bloom filter from the keys, once it has too many removed ones
+    fn refresh_bloom(&mut self) {
+        if let Some(ref mut bloom) = self.bloom {
+            if bloom.is_stale(*self.size) {
+                let mut fresh = bloom.cleared();
+                traverse::visit(self.root.as_mut().as_ref(), |key, _, _| fresh.insert(key));
+                **bloom = fresh;
+            }
+        }
+    }
+}
+
+/// Unicode normalization form of keys in a `TSTMap` created by `TSTMap::with_normalization`.
+#[cfg(feature = "unicode-normalization")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    /// Canonical decomposition followed by canonical composition
+    Nfc,
+    /// Canonical decomposition
+    Nfd,
+    /// Compatibility decomposition followed by canonical composition
+    Nfkc,
+    /// Compatibility decomposition
+    Nfkd,
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl Normalization {
+    fn apply(self, key: &str) -> Cow<'_, str> {
+        // most keys are already in the form, which a quick check tells without copying
+        let quick = match self {
+            Normalization::Nfc => is_nfc_quick(key.chars()),
+            Normalization::Nfd => is_nfd_quick(key.chars()),
+            Normalization::Nfkc => is_nfkc_quick(key.chars()),
+            Normalization::Nfkd => is_nfkd_quick(key.chars()),
+        };
+        if quick == IsNormalized::Yes {
+            return Cow::Borrowed(key);
+        }
+        Cow::Owned(match self {
+            Normalization::Nfc => key.nfc().collect(),
+            Normalization::Nfd => key.nfd().collect(),
+            Normalization::Nfkc => key.nfkc().collect(),
+            Normalization::Nfkd => key.nfkd().collect(),
+        })
+    }
 }
 
 impl<Value: PartialEq> PartialEq for TSTMap<Value> {
@@ -68,14 +317,40 @@ impl<Value: PartialEq> PartialEq for TSTMap<Value> {
 
 impl<Value: Eq> Eq for TSTMap<Value> {}
 
+impl<Value: PartialOrd> PartialOrd for TSTMap<Value> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<Value: Ord> Ord for TSTMap<Value> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
 impl<Value: Clone> Clone for TSTMap<Value> {
     fn clone(&self) -> Self {
         let mut new = TSTMap::<Value>::new();
-        for (k, v) in self.iter() {
-            new.insert(&k, v.clone());
-        }
+        new.clone_from(self);
         new
     }
+
+    /// Copies the structure of `source` in place, reusing already allocated nodes
+    /// and values of the `TSTMap`.
+    fn clone_from(&mut self, source: &Self) {
+        traverse::clone_from(self.root.as_mut(), source.root.as_ref(), &mut self.pool);
+        self.size = source.size;
+        #[cfg(feature = "insertion-order")]
+        {
+            self.next_seq = source.next_seq;
+        }
+        self.form = source.form;
+        self.bloom.clone_from(&source.bloom);
+        self.max_entries = source.max_entries;
+        #[cfg(feature = "insertion-order")]
+        self.inserts.clone_from(&source.inserts);
+    }
 }
 
 impl<Value> TSTMap<Value> {
@@ -90,6 +365,176 @@ impl<Value> TSTMap<Value> {
         Default::default()
     }
 
+    /// Constructs a new, empty `TSTMap<Value>`, which brings every key to the Unicode
+    /// normalization `form` on inserts, lookups and removals. So a key is found
+    /// regardless of the composition it was typed in.
+    ///
+    /// Keys are stored normalized, so iterators return them in `form`,
+    /// which may differ from the inserted ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::{Normalization, TSTMap};
+    ///
+    /// let mut m = TSTMap::with_normalization(Normalization::Nfc);
+    /// m.insert("cafe\u{301}", 1);
+    /// assert_eq!(Some(&1), m.get("caf\u{e9}"));
+    /// assert_eq!(Some(&1), m.get("cafe\u{301}"));
+    /// assert_eq!(vec!["caf\u{e9}"], m.keys().collect::<Vec<_>>());
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    pub fn with_normalization(form: Normalization) -> Self {
+        let mut map = TSTMap::new();
+        map.form.normalization = Some(form);
+        map
+    }
+
+    /// Constructs a new, empty `TSTMap<Value>` with a Bloom filter sized for
+    /// `expected_keys` keys, which lets `contains_key` reject most of missing keys
+    /// without a lookup. Other keys, about 1% of missing ones while the number of keys
+    /// stays within `expected_keys`, are looked up as usual.
+    ///
+    /// Removed keys stay in the filter, which only makes it reject fewer keys, until they
+    /// make up a quarter of `expected_keys`. Then the filter is rebuilt from the keys left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::with_bloom(1000);
+    /// m.insert("abc", 1);
+    /// assert!(m.contains_key("abc"));
+    /// assert!(!m.contains_key("abd"));
+    /// ```
+    pub fn with_bloom(expected_keys: usize) -> Self {
+        let mut map = TSTMap::new();
+        map.bloom = Some(Bloom::new(expected_keys));
+        map
+    }
+
+    /// Constructs a new, empty `TSTMap<Value>`, which keeps at most `max_entries` entries.
+    /// Adding a new key to a full `TSTMap` in any way, like `insert`, `entry`, `merge_with`
+    /// or a cursor, evicts the oldest inserted entry with the `insertion-order` feature,
+    /// or the entry with the smallest key otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_entries` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::with_max_entries(2);
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    /// assert_eq!(2, m.len());
+    /// assert!(!m.contains_key("a"));
+    /// ```
+    pub fn with_max_entries(max_entries: usize) -> Self {
+        assert!(max_entries > 0, "Zero max entries");
+        let mut map = TSTMap::new();
+        map.max_entries = Some(max_entries);
+        map
+    }
+
+    /// Constructs a new, empty `TSTMap<Value>`, which orders chars of keys with `cmp`
+    /// instead of by their code points. All lookups, inserts and iterators follow it,
+    /// so keys are iterated in the order `cmp` makes of them char by char.
+    ///
+    /// `cmp` has to be a total order, where only the same chars are equal.
+    /// Binary formats don't keep `cmp`, so `save` and `write_to` store such a `TSTMap`
+    /// in the order of code points, which it's loaded back in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// // lowercase letters before uppercase ones
+    /// let mut m = TSTMap::with_comparator(|a, b| {
+    ///     (a.is_uppercase(), a).cmp(&(b.is_uppercase(), b))
+    /// });
+    /// m.insert("Apple", 1);
+    /// m.insert("apple", 2);
+    /// m.insert("Banana", 3);
+    /// m.insert("banana", 4);
+    ///
+    /// assert_eq!(vec!["apple", "banana", "Apple", "Banana"], m.keys().collect::<Vec<_>>());
+    /// assert_eq!(Some(&1), m.get("Apple"));
+    /// ```
+    pub fn with_comparator(cmp: fn(char, char) -> Ordering) -> Self {
+        let mut map = TSTMap::new();
+        map.form.char_order = Some(cmp);
+        map
+    }
+
+    /// Constructs a new, empty `TSTMap<Value>`, which stores keys with their chars
+    /// reversed to look them up by their ends, e.g. with `longest_suffix`. Keys are
+    /// reversed on inserts, lookups and removals and turned back by iterators and
+    /// entries, so all methods take and return keys in their original orientation.
+    ///
+    /// Methods working on starts of keys, like `prefix_iter`, `count_prefix`, `cursor`
+    /// or `group_by_first_char`, work on their ends instead, and keys are ordered by
+    /// their chars from the last one. Binary formats don't keep the reversal, so `save`
+    /// and `write_to` store such a `TSTMap` like one of `new`, which it's loaded back as.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new_reversed();
+    /// m.insert("co.uk", 1);
+    /// m.insert("gov.uk", 2);
+    /// m.insert("com", 3);
+    ///
+    /// assert_eq!(Some(&1), m.get("co.uk"));
+    /// assert_eq!("co.uk", m.longest_suffix("bbc.co.uk"));
+    /// assert_eq!(vec!["co.uk", "gov.uk", "com"], m.keys().collect::<Vec<_>>());
+    /// assert_eq!(vec!["co.uk", "gov.uk"], m.prefix_keys(".uk").collect::<Vec<_>>());
+    /// ```
+    pub fn new_reversed() -> Self {
+        let mut map = TSTMap::new();
+        map.form.reversed = true;
+        map
+    }
+
+    // order of chars in nodes
+    fn char_cmp(&self) -> CharCmp {
+        self.form.cmp()
+    }
+
+    // `key` in the form it's stored in the TSTMap
+    fn normalize<'k>(&self, key: &'k str) -> Cow<'k, str> {
+        self.form.apply(key)
+    }
+
+    fn parts(&mut self) -> Parts<'_, Value> {
+        Parts {
+            root: self.root.as_mut(),
+            pool: &mut self.pool,
+            size: &mut self.size,
+            #[cfg(feature = "insertion-order")]
+            next_seq: &mut self.next_seq,
+            bloom: self.bloom.as_mut(),
+            form: self.form,
+            max_entries: self.max_entries,
+            #[cfg(feature = "insertion-order")]
+            inserts: &mut self.inserts,
+        }
+    }
+
+    // bookkeeping of `count` values taken out of the tree
+    fn removed(&mut self, count: usize) {
+        self.size -= count;
+        self.parts().refresh_bloom();
+    }
+
     /// Returns the number of elements in the container.
     ///
     /// # Examples
@@ -111,7 +556,7 @@ impl<Value> TSTMap<Value> {
     ///
     /// # Panics
     ///
-    /// Panics if `key` is empty or more then 2000 symbols(because of reccursion).
+    /// Panics if `key` is empty.
     ///
     /// # Examples
     ///
@@ -123,42 +568,90 @@ impl<Value> TSTMap<Value> {
     /// m.insert("SOmeOtherWOrd", 4);
     /// assert_eq!(2, m.len());
     /// ```
-    pub fn insert(&mut self, key: &str, value: Value) -> Option<Value> {
-        assert!(!key.is_empty(), "Empty key");
-        match self.entry(key) {
-            Occupied(mut entry) => Some(entry.insert(value)),
-            Vacant(entry) => {
-                entry.insert(value);
-                None
-            }
-        }
+    pub fn insert<Key: AsRef<str>>(&mut self, key: Key, value: Value) -> Option<Value> {
+        self.insert_evicting(key, value).0
     }
 
-    /// Gets the given `key`'s corresponding entry in the TSTMap for in-place manipulation.
+    /// Inserts an element like `insert` and also returns the entry evicted to make room
+    /// for it in a `TSTMap` created by `with_max_entries`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is empty.
     ///
     /// # Examples
     ///
     /// ```
     /// use tst::TSTMap;
     ///
-    /// let mut count: TSTMap<usize> = TSTMap::new();
+    /// let mut m = TSTMap::with_max_entries(2);
+    /// assert_eq!((None, None), m.insert_evicting("a", 1));
+    /// assert_eq!((None, None), m.insert_evicting("b", 2));
+    /// assert_eq!((Some(2), None), m.insert_evicting("b", 3));
+    /// assert_eq!((None, Some(("a".to_string(), 1))), m.insert_evicting("c", 4));
+    /// ```
+    pub fn insert_evicting<Key: AsRef<str>>(
+        &mut self,
+        key: Key,
+        value: Value,
+    ) -> (Option<Value>, Option<(String, Value)>) {
+        let (old, _, evicted) = self.insert_full(key.as_ref(), value);
+        (old, evicted)
+    }
+
+    /// Inserts an element like `insert` and returns a mutable reference to the stored
+    /// value, without looking it up again.
     ///
-    /// for x in vec!["abc","bad","abd","cdddd","abc","bade"] {
-    ///     *count.entry(x).or_insert(0) += 1;
-    /// }
+    /// # Panics
     ///
-    /// assert_eq!(2, count["abc"]);
-    /// assert_eq!(1, count["abd"]);
+    /// Panics if `key` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert_mut("abc", vec![]).push(1);
+    /// m.insert_mut("abc", vec![2]).push(3);
+    /// assert_eq!(Some(&vec![2, 3]), m.get("abc"));
     /// ```
-    pub fn entry(&mut self, key: &str) -> Entry<Value> {
+    pub fn insert_mut<Key: AsRef<str>>(&mut self, key: Key, value: Value) -> &mut Value {
+        self.insert_full(key.as_ref(), value).1
+    }
+
+    // inserts `value`, returns the old value, the new one and the evicted entry
+    fn insert_full(
+        &mut self,
+        key: &str,
+        value: Value,
+    ) -> (Option<Value>, &mut Value, Evicted<Value>) {
+        let key = self.normalize(key);
+        self.insert_stored(&key, value)
+    }
+
+    // `insert_full` of a key already in the form it's stored in
+    fn insert_stored(
+        &mut self,
+        key: &str,
+        value: Value,
+    ) -> (Option<Value>, &mut Value, Evicted<Value>) {
         assert!(!key.is_empty(), "Empty key");
-        let l = &mut self.size;
-        let cur = traverse::insert(self.root.as_mut(), key, &mut self.pool);
-        Entry::<Value>::new(cur, l)
+        let mut parts = self.parts();
+        let (path, node, evicted) = parts.locate(key);
+        match node.value {
+            Some(ref mut stored) => (Some(mem::replace(stored, value)), stored, evicted),
+            None => (None, parts.put(path, node, key, value), evicted),
+        }
     }
 
-    /// Removes a `key` from the TSTMap, returning the value at the key if the key
-    /// was previously in the TSTMap.
+    /// Inserts an element like `insert` and also returns the number of steps to nodes
+    /// with smaller or larger chars taken on the way to the `key`.
+    /// The more of them, the less balanced the tree is for the `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `key` is empty.
     ///
     /// # Examples
     ///
@@ -166,38 +659,57 @@ impl<Value> TSTMap<Value> {
     /// use tst::TSTMap;
     ///
     /// let mut m = TSTMap::new();
-    /// m.insert("abc", 100);
-    /// assert_eq!(Some(100), m.remove("abc"));
-    /// assert_eq!(None, m.remove("abc"));
+    /// assert_eq!((None, 0), m.insert_reporting_depth("b", 1));
+    /// assert_eq!((None, 1), m.insert_reporting_depth("c", 2));
+    /// assert_eq!((None, 2), m.insert_reporting_depth("d", 3));
+    /// assert_eq!((Some(2), 1), m.insert_reporting_depth("c", 4));
     /// ```
-    pub fn remove(&mut self, key: &str) -> Option<Value> {
-        let ret = traverse::remove(self.root.as_mut(), key);
-        if ret.is_some() {
-            self.size -= 1;
+    pub fn insert_reporting_depth<Key: AsRef<str>>(
+        &mut self,
+        key: Key,
+        value: Value,
+    ) -> (Option<Value>, usize) {
+        let key = self.normalize(key.as_ref());
+        assert!(!key.is_empty(), "Empty key");
+        let mut parts = self.parts();
+        let (path, cur, _) = parts.locate(&key);
+        // the path has a node for every char of the key and one for every side step
+        let side_steps = path.depth() - key.chars().count();
+        if cur.value.is_some() {
+            return (cur.value.replace(value), side_steps);
         }
-        ret
+        parts.put(path, cur, &key, value);
+        (None, side_steps)
     }
 
-    /// Returns a reference to the value corresponding to the `key` or None.
+    /// Inserts a filesystem path as a key. Paths, which aren't valid Unicode,
+    /// are converted lossily, with invalid sequences replaced by `U+FFFD`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path is empty.
     ///
     /// # Examples
     ///
     /// ```
+    /// use std::path::Path;
     /// use tst::TSTMap;
     ///
     /// let mut m = TSTMap::new();
-    /// m.insert("first", 13);
-    /// assert_eq!(Some(&13), m.get("first"));
-    /// assert_eq!(None, m.get("second"));
+    /// m.insert_path(Path::new("/usr/bin"), 1);
+    /// assert_eq!(Some(&1), m.get("/usr/bin"));
     /// ```
-    pub fn get(&self, key: &str) -> Option<&Value> {
-        match traverse::search(self.root.as_ref(), key) {
-            None => None,
-            Some(ptr) => ptr.value.as_ref(),
-        }
+    pub fn insert_path(&mut self, path: &Path, value: Value) -> Option<Value> {
+        self.insert(path.to_string_lossy(), value)
     }
 
-    /// Returns a mutable reference to the value corresponding to the `key`.
+    /// Inserts `key` with its chars reversed, to look up its suffixes with
+    /// `longest_suffix` later. Iterators and other accessors see the reversed key,
+    /// unless the `TSTMap` is created by `new_reversed`, where it's the same as `insert`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is empty.
     ///
     /// # Examples
     ///
@@ -205,20 +717,27 @@ impl<Value> TSTMap<Value> {
     /// use tst::TSTMap;
     ///
     /// let mut m = TSTMap::new();
-    /// m.insert("first", 13);
-    /// if let Some(x) = m.get_mut("first") {
-    ///     *x = -13;
-    /// }
-    /// assert_eq!(-13, m["first"]);
+    /// m.insert_reversed("co.uk", 1);
+    /// assert_eq!(Some(&1), m.get("ku.oc"));
+    ///
+    /// let mut m = TSTMap::new_reversed();
+    /// m.insert_reversed("co.uk", 1);
+    /// assert_eq!(Some(&1), m.get("co.uk"));
     /// ```
-    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
-        match traverse::search_mut(self.root.as_ref_mut(), key) {
-            None => None,
-            Some(ptr) => ptr.value.as_mut(),
-        }
+    pub fn insert_reversed<Key: AsRef<str>>(&mut self, key: Key, value: Value) -> Option<Value> {
+        let form = KeyForm {
+            reversed: true,
+            ..self.form
+        };
+        self.insert_stored(&form.apply(key.as_ref()), value).0
     }
 
-    /// Returns true if the `TSTMap` contains a value for the specified `key`.
+    /// Moves all nodes of the `TSTMap` into a new memory pool in an order, where a node
+    /// is mostly followed by its next char, so lookups touch fewer cache lines.
+    /// It also releases the memory of nodes left after removals.
+    ///
+    /// It is worth calling once after building a big `TSTMap`, which is only read afterwards.
+    ///
     /// # Examples
     ///
     /// ```
@@ -226,15 +745,1987 @@ impl<Value> TSTMap<Value> {
     ///
     /// let mut m = TSTMap::new();
     /// m.insert("abc", 1);
-    /// assert!(!m.contains_key("ab"));
-    /// assert!(m.contains_key("abc"))
+    /// m.insert("abd", 2);
+    /// m.remove("abd");
+    ///
+    /// m.optimize_layout();
+    /// assert_eq!(1, m.len());
+    /// assert_eq!(Some(&1), m.get("abc"));
     /// ```
-    #[inline]
-    pub fn contains_key(&self, key: &str) -> bool {
-        self.get(key).is_some()
+    pub fn optimize_layout(&mut self) {
+        let mut pool = Herd::new();
+        let mut root = BoxedNode::default();
+        traverse::relayout(root.as_mut(), self.root.as_mut(), &mut pool);
+        self.root = root;
+        self.pool = pool;
+    }
+
+    /// Rebuilds the `TSTMap` in a canonical balanced shape, which depends only on
+    /// the set of keys and not on the order they were inserted in. So equal maps
+    /// become equal node by node, e.g. `write_to` gives the same bytes for them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut a = TSTMap::new();
+    /// a.insert("abc", 1);
+    /// a.insert("abd", 2);
+    /// let mut b = TSTMap::new();
+    /// b.insert("abd", 2);
+    /// b.insert("abc", 1);
+    ///
+    /// a.canonicalize();
+    /// b.canonicalize();
+    /// let (mut bytes_a, mut bytes_b) = (vec![], vec![]);
+    /// a.write_to(&mut bytes_a).unwrap();
+    /// b.write_to(&mut bytes_b).unwrap();
+    /// assert_eq!(bytes_a, bytes_b);
+    /// ```
+    pub fn canonicalize(&mut self) {
+        let mut pool = Herd::new();
+        let mut root = BoxedNode::default();
+        let cmp = self.char_cmp();
+        traverse::rebalance(root.as_mut(), self.root.as_mut(), &mut pool, cmp);
+        self.root = root;
+        self.pool = pool;
+    }
+
+    /// Turns the `TSTMap` into a read-only `FrozenTST`, which could be shared between
+    /// threads without locks. The layout of nodes is optimized on the way,
+    /// see `optimize_layout`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// let frozen = Arc::new(m.freeze());
+    ///
+    /// let shared = frozen.clone();
+    /// let found = thread::spawn(move || shared.get("abc").copied()).join().unwrap();
+    /// assert_eq!(Some(1), found);
+    /// ```
+    pub fn freeze(self) -> FrozenTST<Value> {
+        FrozenTST::new(self)
+    }
+
+    /// Moves all elements from `other` into the `TSTMap`, calling `resolve` with the
+    /// existing and incoming values when a key is present in both.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    ///
+    /// let mut other = TSTMap::new();
+    /// other.insert("b", 10);
+    /// other.insert("c", 20);
+    ///
+    /// m.merge_with(other, |existing, incoming| *existing += incoming);
+    /// assert_eq!(3, m.len());
+    /// assert_eq!(12, m["b"]);
+    /// assert_eq!(20, m["c"]);
+    /// ```
+    pub fn merge_with<F: FnMut(&mut Value, Value)>(
+        &mut self,
+        other: TSTMap<Value>,
+        mut resolve: F,
+    ) {
+        let mut parts = self.parts();
+        for (key, value) in other {
+            // keys of `other` may be in another form
+            let key = parts.form.apply(&key).into_owned();
+            let mut incoming = Some(value);
+            let existing = parts.get_or_insert_with(&key, || incoming.take().unwrap());
+            if let Some(value) = incoming {
+                resolve(existing, value);
+            }
+        }
+    }
+
+    /// Builds a `TSTMap` from `iter` like `from_iter`, but returns the key and the value
+    /// of the first entry with a duplicate key as an error instead of replacing
+    /// the earlier value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let m = TSTMap::try_from_iter_unique(vec![("a", 1), ("b", 2)]).unwrap();
+    /// assert_eq!(2, m.len());
+    ///
+    /// let dup = TSTMap::try_from_iter_unique(vec![("a", 1), ("b", 2), ("a", 3)]);
+    /// assert_eq!(Some(("a".to_string(), 3)), dup.err());
+    /// ```
+    pub fn try_from_iter_unique<I, Key>(iter: I) -> Result<Self, (String, Value)>
+    where
+        I: IntoIterator<Item = (Key, Value)>,
+        Key: AsRef<str>,
+    {
+        let mut m = TSTMap::new();
+        for (key, value) in iter {
+            match m.entry(key.as_ref()) {
+                Occupied(entry) => return Err((entry.key().to_string(), value)),
+                Vacant(entry) => {
+                    entry.insert(value);
+                }
+            }
+        }
+        Ok(m)
+    }
+
+    /// Inserts all pairs of `iter` like `extend`, but skips pairs with empty keys instead
+    /// of panicking, returning the number of skipped pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// assert_eq!(2, m.extend_lossy(vec![("a", 1), ("", 2), ("b", 3), ("", 4)]));
+    /// assert_eq!(2, m.len());
+    /// ```
+    pub fn extend_lossy<I, Key>(&mut self, iter: I) -> usize
+    where
+        I: IntoIterator<Item = (Key, Value)>,
+        Key: AsRef<str>,
+    {
+        let mut skipped = 0;
+        for (key, value) in iter {
+            if key.as_ref().is_empty() {
+                skipped += 1;
+            } else {
+                self.insert(key, value);
+            }
+        }
+        skipped
+    }
+
+    /// Builds a `TSTMap` from `r` with one `key\tvalue` pair per line, where values are
+    /// parsed with `parse`. Blank lines are skipped and later values of the same key
+    /// replace earlier ones.
+    ///
+    /// Returns an error of kind `InvalidData` for a line without a tab, with an empty key
+    /// or a value `parse` fails on, as well as any error of reading from `r`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let data = "one\t1\ntwo\t2\n\nthree\t3\n";
+    /// let m = TSTMap::from_reader(data.as_bytes(), |v| v.parse::<i32>()).unwrap();
+    /// assert_eq!(3, m.len());
+    /// assert_eq!(2, m["two"]);
+    ///
+    /// assert!(TSTMap::from_reader("one\tx\n".as_bytes(), |v| v.parse::<i32>()).is_err());
+    /// ```
+    pub fn from_reader<R, F, E>(r: R, mut parse: F) -> io::Result<Self>
+    where
+        R: BufRead,
+        F: FnMut(&str) -> Result<Value, E>,
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        let invalid = |n: usize, msg: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", n + 1, msg),
+            )
+        };
+        let mut map = TSTMap::new();
+        for (n, line) in r.lines().enumerate() {
+            let line = line?;
+            let line = line.strip_suffix('\r').unwrap_or(&line);
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('\t')
+                .ok_or_else(|| invalid(n, "no tab between key and value".to_string()))?;
+            if key.is_empty() {
+                return Err(invalid(n, "empty key".to_string()));
+            }
+            let value = parse(value).map_err(|err| invalid(n, err.into().to_string()))?;
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+
+    /// Returns a mutable reference to the value of `key`, inserting the result of `f`
+    /// first if the `key` is not in the TSTMap. It's a shortcut for
+    /// `entry(key).or_insert_with(f)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// for word in ["a", "b", "a"] {
+    ///     m.get_or_insert_with(word, Vec::new).push(word.len());
+    /// }
+    /// assert_eq!(2, m.len());
+    /// assert_eq!(vec![1, 1], m["a"]);
+    /// ```
+    pub fn get_or_insert_with<Key, F>(&mut self, key: Key, f: F) -> &mut Value
+    where
+        Key: AsRef<str>,
+        F: FnOnce() -> Value,
+    {
+        let key = self.normalize(key.as_ref());
+        assert!(!key.is_empty(), "Empty key");
+        self.parts().get_or_insert_with(&key, f)
+    }
+
+    /// Gets the given `key`'s corresponding entry in the TSTMap for in-place manipulation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut count: TSTMap<usize> = TSTMap::new();
+    ///
+    /// for x in vec!["abc","bad","abd","cdddd","abc","bade"] {
+    ///     *count.entry(x).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(2, count["abc"]);
+    /// assert_eq!(1, count["abd"]);
+    /// ```
+    pub fn entry<'k>(&'k mut self, key: &'k str) -> Entry<'k, Value> {
+        let key = self.form.normalize(key);
+        assert!(!key.is_empty(), "Empty key");
+        Entry::new(self, key)
+    }
+
+    /// Gets the given `key`'s corresponding entry like `entry` does, but takes the `key`
+    /// by value and keeps it in the entry without copying.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m: TSTMap<usize> = TSTMap::new();
+    ///
+    /// for i in 0..3 {
+    ///     let entry = m.entry_owned(format!("key{}", i % 2));
+    ///     assert_eq!(4, entry.key().len());
+    ///     *entry.or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(Some(&2), m.get("key0"));
+    /// assert_eq!(Some(&1), m.get("key1"));
+    /// ```
+    pub fn entry_owned(&mut self, key: String) -> Entry<'_, Value> {
+        let key = self.form.changes(&key).unwrap_or(key);
+        assert!(!key.is_empty(), "Empty key");
+        Entry::new(self, Cow::Owned(key))
+    }
+
+    /// Gets a cursor at `prefix` to insert and look up keys starting with it by their
+    /// suffixes, without descending from the root for every key.
+    /// The nodes of `prefix` are created only when a key is inserted through the cursor.
+    ///
+    /// In a `TSTMap` created by `new_reversed` the cursor is at keys ending with `prefix`,
+    /// and the suffixes of `insert_suffix` and `get_suffix` go in front of it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// let mut cursor = m.cursor("user:1:");
+    /// cursor.insert_suffix("name", "ann");
+    /// cursor.insert_suffix("email", "ann@example.com");
+    /// assert_eq!(Some(&"ann"), cursor.get_suffix("name"));
+    ///
+    /// assert_eq!(2, m.len());
+    /// assert_eq!(Some(&"ann@example.com"), m.get("user:1:email"));
+    /// ```
+    pub fn cursor(&mut self, prefix: &str) -> Cursor<'_, Value> {
+        let prefix = self.form.normalize(prefix).into_owned();
+        let parts = self.parts();
+        let found = if prefix.is_empty() {
+            None
+        } else {
+            let stored = parts.form.orient(Cow::Borrowed(&prefix));
+            traverse::descend_mut(parts.root.clone(), &stored, parts.form.cmp())
+        };
+        let (path, node) = match found {
+            Some((path, node)) => (path, Some(node)),
+            None => (KeyPath::default(), None),
+        };
+        Cursor {
+            parts,
+            node,
+            path,
+            prefix,
+        }
+    }
+
+    /// Removes a `key` from the TSTMap, returning the value at the key if the key
+    /// was previously in the TSTMap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 100);
+    /// assert_eq!(Some(100), m.remove("abc"));
+    /// assert_eq!(None, m.remove("abc"));
+    /// ```
+    pub fn remove<Key: AsRef<str>>(&mut self, key: Key) -> Option<Value> {
+        self.remove_and_report(key).map(first)
+    }
+
+    /// Removes a `key` from the TSTMap like `remove`, returning the value along with
+    /// the number of nodes, which were detached as no other key goes through them.
+    /// Their memory is only released by `optimize_layout`, so the sum of these numbers
+    /// tells when it's worth calling.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcde", 2);
+    /// assert_eq!(Some((2, 2)), m.remove_and_report("abcde"));
+    /// assert_eq!(Some((1, 3)), m.remove_and_report("abc"));
+    /// assert_eq!(None, m.remove_and_report("abc"));
+    /// ```
+    pub fn remove_and_report<Key: AsRef<str>>(&mut self, key: Key) -> Option<(Value, usize)> {
+        let key = self.normalize(key.as_ref());
+        let cmp = self.char_cmp();
+        let ret = traverse::remove(self.root.as_mut(), &key, cmp);
+        if ret.is_some() {
+            self.removed(1);
+        }
+        ret
+    }
+
+    /// Removes a `key` from the TSTMap only if `pred` accepts its value, returning
+    /// the removed value. The value stays in the TSTMap if `pred` rejects it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 100);
+    /// assert_eq!(None, m.remove_if("abc", |v| *v == 1));
+    /// assert_eq!(1, m.len());
+    /// assert_eq!(Some(100), m.remove_if("abc", |v| *v == 100));
+    /// assert_eq!(0, m.len());
+    /// ```
+    pub fn remove_if<Key, F>(&mut self, key: Key, pred: F) -> Option<Value>
+    where
+        Key: AsRef<str>,
+        F: FnOnce(&Value) -> bool,
+    {
+        let key = self.normalize(key.as_ref());
+        let cmp = self.char_cmp();
+        let ret = traverse::remove_if(self.root.as_mut(), &key, cmp, pred).map(first);
+        if ret.is_some() {
+            self.removed(1);
+        }
+        ret
+    }
+
+    /// Removes a `key` from the TSTMap like `remove`, but keeps the nodes of the `key`,
+    /// so inserting it again doesn't allocate them. Use `purge_tombstones` to detach
+    /// such nodes without values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 100);
+    /// assert_eq!(Some(100), m.soft_remove("abc"));
+    /// assert_eq!(0, m.len());
+    /// assert_eq!(None, m.get("abc"));
+    /// assert_eq!(3, m.stats().nodes);
+    /// ```
+    pub fn soft_remove<Key: AsRef<str>>(&mut self, key: Key) -> Option<Value> {
+        let key = self.normalize(key.as_ref());
+        let cmp = self.char_cmp();
+        let ret = traverse::soft_remove(self.root.as_mut(), &key, cmp);
+        if ret.is_some() {
+            self.removed(1);
+        }
+        ret
+    }
+
+    /// Detaches all nodes, which have no values below them, like ones left by
+    /// `soft_remove`. Their memory is released by `optimize_layout`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("ab", 1);
+    /// m.insert("abcd", 2);
+    /// m.soft_remove("abcd");
+    /// assert_eq!(4, m.stats().nodes);
+    ///
+    /// m.purge_tombstones();
+    /// assert_eq!(2, m.stats().nodes);
+    /// assert_eq!(Some(&1), m.get("ab"));
+    /// ```
+    pub fn purge_tombstones(&mut self) {
+        traverse::prune(self.root.as_mut());
+    }
+
+    /// Creates an iterator, which removes and yields entries for which `pred` returns
+    /// true, in sorted order of keys. Entries, which weren't yielded because the iterator
+    /// wasn't consumed to the end, stay in the TSTMap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    /// m.insert("d", 4);
+    ///
+    /// let odd: Vec<_> = m.drain_filter(|_, v| *v % 2 == 1).collect();
+    /// assert_eq!(vec![("a".to_string(), 1), ("c".to_string(), 3)], odd);
+    /// assert_eq!(2, m.len());
+    ///
+    /// assert_eq!(Some(("b".to_string(), 2)), m.drain_filter(|_, _| true).next());
+    /// assert_eq!(Some(&4), m.get("d"));
+    /// ```
+    pub fn drain_filter<F>(&mut self, pred: F) -> DrainFilter<'_, Value, F>
+    where
+        F: FnMut(&str, &mut Value) -> bool,
+    {
+        let root = self.root.as_mut();
+        DrainFilter {
+            iter: Traverse::new(root.as_mut().as_ref()),
+            root,
+            cont_size: &mut self.size,
+            form: self.form,
+            pred,
+            removed: false,
+        }
+    }
+
+    /// Removes a `key` from the TSTMap, returning the stored key and value if the key
+    /// was previously in the TSTMap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 100);
+    /// assert_eq!(Some(("abc".to_string(), 100)), m.remove_entry("abc"));
+    /// assert_eq!(None, m.remove_entry("abc"));
+    /// ```
+    pub fn remove_entry<Key: AsRef<str>>(&mut self, key: Key) -> Option<(String, Value)> {
+        let key = self.form.normalize(key.as_ref());
+        self.remove(key.as_ref())
+            .map(|value| (key.into_owned(), value))
+    }
+
+    /// Moves the value stored at `from` to `to`, replacing the value of `to` if there was
+    /// one. Returns true if `from` was in the TSTMap, otherwise the TSTMap is left intact.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `to` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("colour", 1);
+    /// m.insert("color", 2);
+    ///
+    /// assert!(m.rename("colour", "color"));
+    /// assert_eq!(1, m.len());
+    /// assert_eq!(Some(&1), m.get("color"));
+    /// assert!(!m.rename("colour", "color"));
+    /// ```
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        assert!(!to.is_empty(), "Empty key");
+        match self.remove(from) {
+            Some(value) => {
+                self.insert(to, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes every key starting with `prefix` (including `prefix` itself),
+    /// returning the number of removed elements.
+    ///
+    /// The whole subtree below `prefix` is detached at once, instead of removing
+    /// keys one by one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("cache:a", 1);
+    /// m.insert("cache:b", 2);
+    /// m.insert("cache", 3);
+    /// m.insert("config", 4);
+    ///
+    /// assert_eq!(3, m.clear_prefix("cache"));
+    /// assert_eq!(1, m.len());
+    /// assert_eq!(Some(&4), m.get("config"));
+    /// ```
+    pub fn clear_prefix(&mut self, prefix: &str) -> usize {
+        if prefix.is_empty() {
+            let count = self.len();
+            self.clear();
+            return count;
+        }
+        let prefix = self.normalize(prefix);
+        let cmp = self.char_cmp();
+        let count = traverse::clear_prefix(self.root.as_mut(), &prefix, cmp);
+        self.removed(count);
+        count
+    }
+
+    /// Removes every key, which is longer than `prefix` and starts with it, returning
+    /// the number of removed elements. Unlike `clear_prefix`, `prefix` itself stays.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("dir", 1);
+    /// m.insert("dir/a", 2);
+    /// m.insert("dir/b", 3);
+    /// m.insert("dirt", 4);
+    ///
+    /// assert_eq!(3, m.clear_children("dir"));
+    /// assert_eq!(1, m.len());
+    /// assert_eq!(Some(&1), m.get("dir"));
+    /// ```
+    pub fn clear_children(&mut self, prefix: &str) -> usize {
+        if prefix.is_empty() {
+            return self.clear_prefix(prefix);
+        }
+        let prefix = self.normalize(prefix);
+        let cmp = self.char_cmp();
+        let count = traverse::clear_children(self.root.as_mut(), &prefix, cmp);
+        self.removed(count);
+        count
+    }
+
+    /// Returns the number of keys starting with `pref` (including `pref` itself).
+    ///
+    /// It takes time proportional to the length of `pref`, as every node keeps
+    /// the number of values in its subtree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 2);
+    /// m.insert("abe", 3);
+    /// m.insert("b", 4);
+    ///
+    /// assert_eq!(3, m.count_prefix("ab"));
+    /// assert_eq!(2, m.count_prefix("abc"));
+    /// assert_eq!(0, m.count_prefix("x"));
+    /// assert_eq!(4, m.count_prefix(""));
+    /// ```
+    pub fn count_prefix(&self, pref: &str) -> usize {
+        if pref.is_empty() {
+            return self.len();
+        }
+        let pref = self.normalize(pref);
+        traverse::count_prefix(self.root.as_ref(), &pref, self.char_cmp())
+    }
+
+    /// Returns the key and the value of the `n`-th entry in the order of keys,
+    /// counting from zero, or None if there are not so many entries.
+    ///
+    /// It takes time proportional to the length of the found key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    /// m.insert("ab", 3);
+    ///
+    /// assert_eq!(Some(("a".to_string(), &1)), m.nth(0));
+    /// assert_eq!(Some(("ab".to_string(), &3)), m.nth(1));
+    /// assert_eq!(Some(("b".to_string(), &2)), m.nth(2));
+    /// assert_eq!(None, m.nth(3));
+    /// ```
+    pub fn nth(&self, n: usize) -> Option<(String, &Value)> {
+        traverse::nth(self.root.as_ref(), n).map(|entry| self.form.restore(entry))
+    }
+
+    /// Returns the number of keys smaller than `key`, which is the position of `key`
+    /// in the order of keys, if the TSTMap contains it.
+    ///
+    /// It takes time proportional to the length of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    /// m.insert("ab", 3);
+    ///
+    /// assert_eq!(0, m.rank("a"));
+    /// assert_eq!(2, m.rank("b"));
+    /// assert_eq!(2, m.rank("aba"));
+    /// assert_eq!(3, m.rank("c"));
+    /// ```
+    pub fn rank<Key: AsRef<str>>(&self, key: Key) -> usize {
+        let key = self.normalize(key.as_ref());
+        traverse::rank(self.root.as_ref(), &key, self.char_cmp())
+    }
+
+    /// Returns a reference to the value corresponding to the `key` or None.
+    ///
+    /// Like other accessors, it takes any key type implementing `AsRef<str>`,
+    /// e.g. `&str`, `String`, `&String` or `Cow<str>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("first", 13);
+    /// assert_eq!(Some(&13), m.get("first"));
+    /// assert_eq!(Some(&13), m.get("first".to_string()));
+    /// assert_eq!(None, m.get("second"));
+    /// ```
+    pub fn get<Key: AsRef<str>>(&self, key: Key) -> Option<&Value> {
+        let key = self.normalize(key.as_ref());
+        match traverse::search(self.root.as_ref(), &key, self.char_cmp()) {
+            None => None,
+            Some(ptr) => ptr.value.as_ref(),
+        }
+    }
+
+    /// Returns a mutable reference to the value corresponding to the `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("first", 13);
+    /// if let Some(x) = m.get_mut("first") {
+    ///     *x = -13;
+    /// }
+    /// assert_eq!(-13, m["first"]);
+    /// ```
+    pub fn get_mut<Key: AsRef<str>>(&mut self, key: Key) -> Option<&mut Value> {
+        let key = self.normalize(key.as_ref());
+        let cmp = self.char_cmp();
+        match traverse::search_mut(self.root.as_ref_mut(), &key, cmp) {
+            None => None,
+            Some(ptr) => ptr.value.as_mut(),
+        }
+    }
+
+    /// Returns references to the values corresponding to each of `keys`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("first", 1);
+    /// m.insert("second", 2);
+    /// assert_eq!(vec![Some(&2), None, Some(&1)], m.get_many(&["second", "third", "first"]));
+    /// ```
+    pub fn get_many(&self, keys: &[&str]) -> Vec<Option<&Value>> {
+        keys.iter().map(|key| self.get(key)).collect()
+    }
+
+    /// Returns mutable references to the values corresponding to each of `keys` at once.
+    /// Returns None if any of `keys` is missing or repeated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("first", 1);
+    /// m.insert("second", 2);
+    /// if let Some(values) = m.get_many_mut(&["first", "second"]) {
+    ///     for value in values {
+    ///         *value += 10;
+    ///     }
+    /// }
+    /// assert_eq!(11, m["first"]);
+    /// assert_eq!(12, m["second"]);
+    ///
+    /// assert!(m.get_many_mut(&["first", "first"]).is_none());
+    /// assert!(m.get_many_mut(&["first", "third"]).is_none());
+    /// ```
+    pub fn get_many_mut(&mut self, keys: &[&str]) -> Option<Vec<&mut Value>> {
+        let mut ptrs = Vec::with_capacity(keys.len());
+        for key in keys {
+            ptrs.push(self.get_mut(key)? as *mut Value);
+        }
+        let mut sorted = ptrs.clone();
+        sorted.sort_unstable();
+        if sorted.windows(2).any(|pair| pair[0] == pair[1]) {
+            return None;
+        }
+        // all pointers are distinct, so the references don't alias
+        Some(ptrs.into_iter().map(|ptr| unsafe { &mut *ptr }).collect())
+    }
+
+    /// Returns true if the `TSTMap` contains a value for the specified `key`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// assert!(!m.contains_key("ab"));
+    /// assert!(m.contains_key("abc"))
+    /// ```
+    #[inline]
+    pub fn contains_key<Key: AsRef<str>>(&self, key: Key) -> bool {
+        if let Some(ref bloom) = self.bloom {
+            if !bloom.contains(&self.normalize(key.as_ref())) {
+                return false;
+            }
+        }
+        self.get(key).is_some()
+    }
+
+    /// Returns true if the `TSTMap` contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// assert!(m.is_empty());
+    ///
+    /// m.insert("abc", 1);
+    /// assert!(!m.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Clears the `TSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 100);
+    /// m.clear();
+    ///
+    /// assert!(m.is_empty());
+    /// assert_eq!(None, m.get("abc"));
+    /// ```
+    pub fn clear(&mut self) {
+        *self = self.empty_like();
+    }
+
+    // a new empty map with the same settings
+    fn empty_like(&self) -> Self {
+        let mut map = TSTMap::new();
+        map.form = self.form;
+        map.bloom = self.bloom.as_ref().map(Bloom::cleared);
+        map.max_entries = self.max_entries;
+        map
+    }
+
+    /// An iterator returning all nodes matching wildcard pattern `pat`.
+    /// Iterator element type is (String, V)
+    ///
+    /// In the pattern `.` matches any character, `[abc]` matches one of the characters
+    /// in brackets and `[^abc]` matches any character except them.
+    /// Every other character, including `[` without closing `]`, matches itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    ///
+    /// for (k, v) in m.wildcard_iter(".") {
+    ///     println!("{} -> {}", k, v);
+    /// }
+    ///
+    /// assert_eq!(4, m.wildcard_iter("[ac]").map(|(_, v)| v).sum());
+    /// assert_eq!(5, m.wildcard_iter("[^a]").map(|(_, v)| v).sum());
+    /// ```
+    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<Value> {
+        WildCardIter::new(self.root.as_ref(), pat, self.len(), self.form)
+    }
+
+    /// An iterator returning all entries with keys ending with a match of wildcard
+    /// pattern `pat`, which has the same syntax as in `wildcard_iter`, in sorted order.
+    /// All keys are checked one by one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("sing", 1);
+    /// m.insert("string", 2);
+    /// m.insert("walking", 3);
+    /// m.insert("ingot", 4);
+    ///
+    /// let keys: Vec<String> = m.suffix_wildcard_iter("..ing").map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["string", "walking"], keys);
+    /// ```
+    pub fn suffix_wildcard_iter(&self, pat: &str) -> SuffixWildCardIter<'_, Value> {
+        SuffixWildCardIter {
+            iter: self.iter(),
+            pat: SuffixPattern::new(pat),
+        }
+    }
+
+    /// An iterator returning all entries with keys matching the regular expression `re`
+    /// as a whole, as if it was surrounded by `^` and `$`.
+    ///
+    /// Options set with `regex::RegexBuilder` are not kept, use inline flags
+    /// like `(?i)` in the pattern instead. All keys are checked one by one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use regex::Regex;
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("get_name", 1);
+    /// m.insert("get_2", 2);
+    /// m.insert("set_name", 3);
+    /// m.insert("get_name_mut", 4);
+    ///
+    /// let re = Regex::new("get_[a-z]+").unwrap();
+    /// let keys: Vec<String> = m.regex_iter(&re).map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["get_name"], keys);
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn regex_iter(&self, re: &Regex) -> RegexIter<'_, Value> {
+        RegexIter::new(self.iter(), re)
+    }
+
+    /// An mutable iterator returning all nodes matching wildcard pattern `pat`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    ///
+    /// for (k, v) in m.wildcard_iter_mut(".") {
+    ///     *v += 10;
+    /// }
+    /// assert_eq!(11, m["a"]);
+    /// assert_eq!(12, m["b"]);
+    /// assert_eq!(13, m["c"]);
+    /// ```
+    pub fn wildcard_iter_mut(&mut self, pat: &str) -> WildCardIterMut<Value> {
+        WildCardIterMut::new(self.root.as_ref_mut(), pat, self.len(), self.form)
+    }
+
+    /// Method returns iterator over all keys with common prefix `pref` in the `TSTMap`,
+    /// yielding only the rest of every key after `pref` with its value.
+    /// A key equal to `pref` itself is yielded as an empty string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("rece", 1);
+    /// m.insert("receive", 2);
+    /// m.insert("receiver", 3);
+    /// m.insert("recipe", 4);
+    ///
+    /// let mut suffixes: Vec<String> = m.completions("rece").map(|(s, _)| s).collect();
+    /// suffixes.sort();
+    /// assert_eq!(vec!["", "ive", "iver"], suffixes);
+    /// ```
+    pub fn completions(&self, pref: &str) -> Iter<'_, Value> {
+        let pref = self.normalize(pref);
+        let node = traverse::search(self.root.as_ref(), &pref, self.char_cmp());
+        Iter::with_prefix(node, "", self.form)
+    }
+
+    /// Method returns iterator over all values with common prefix `pref` in the `TSTMap`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    /// m.insert("abce", 1);
+    /// m.insert("abca", 1);
+    /// m.insert("zxd", 1);
+    /// m.insert("add", 1);
+    /// m.insert("abcdef", 1);
+    ///
+    /// for (key, value) in m.prefix_iter("abc") {
+    ///     println!("{}: {}", key, value);
+    /// }
+    ///
+    /// ```
+    pub fn prefix_iter(&self, pref: &str) -> Iter<Value> {
+        let pref = self.normalize(pref);
+        let node = traverse::search(self.root.as_ref(), &pref, self.char_cmp());
+        Iter::with_prefix(node, &pref, self.form)
+    }
+
+    /// Returns an iterator over groups of entries with the same first char of keys,
+    /// yielding the char and an iterator over entries of the group like `prefix_iter`
+    /// for the char does. Groups come in the order of chars.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("banana", 1);
+    /// m.insert("apple", 2);
+    /// m.insert("blueberry", 3);
+    ///
+    /// let index: Vec<(char, usize)> = m.group_by_first_char().map(|(c, it)| (c, it.count())).collect();
+    /// assert_eq!(vec![('a', 1), ('b', 2)], index);
+    /// ```
+    pub fn group_by_first_char(&self) -> FirstCharGroups<'_, Value> {
+        FirstCharGroups {
+            nodes: traverse::first_level(self.root.as_ref()).into_iter(),
+            form: self.form,
+        }
+    }
+
+    /// Splits the `TSTMap` into maps of keys with the same first char, in the order
+    /// of chars. Shards keep the normalization of keys and their insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("banana", 1);
+    /// m.insert("apple", 2);
+    /// m.insert("blueberry", 3);
+    ///
+    /// let shards = m.shard_by_first_char();
+    /// assert_eq!(2, shards.len());
+    /// let (c, ref b) = shards[1];
+    /// assert_eq!('b', c);
+    /// assert_eq!(2, b.len());
+    /// assert_eq!(Some(&3), b.get("blueberry"));
+    /// ```
+    pub fn shard_by_first_char(mut self) -> Vec<(char, TSTMap<Value>)> {
+        let chars: Vec<char> = traverse::first_level(self.root.as_ref())
+            .iter()
+            .map(|node| node.c)
+            .collect();
+        let mut shards = Vec::with_capacity(chars.len());
+        let cmp = self.char_cmp();
+        for c in chars {
+            let mut shard = TSTMap::new();
+            traverse::move_branch(
+                shard.root.as_mut(),
+                self.root.as_mut(),
+                c,
+                &mut shard.pool,
+                cmp,
+            );
+            shard.size = shard.root.size();
+            shard.form = self.form;
+            #[cfg(feature = "insertion-order")]
+            {
+                shard.next_seq = self.next_seq;
+            }
+            // nodes left by removals may have no entries below
+            if shard.size > 0 {
+                shards.push((c, shard));
+            }
+        }
+        self.size = 0;
+        shards
+    }
+
+    /// Method returns iterator over all keys, which start with something within Levenshtein
+    /// distance `max_dist` of `pref`, in sorted order. All completions of such a start are
+    /// yielded, so typos in the typed part of a key are tolerated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("receiver", 1);
+    /// m.insert("receive", 2);
+    /// m.insert("recipe", 3);
+    /// m.insert("deceive", 4);
+    ///
+    /// let keys: Vec<String> = m.fuzzy_prefix_iter("recieve", 2).map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["receive", "receiver", "recipe"], keys);
+    /// ```
+    pub fn fuzzy_prefix_iter(&self, pref: &str, max_dist: usize) -> FuzzyPrefixIter<'_, Value> {
+        FuzzyPrefixIter {
+            iter: FuzzyPrefixTraverse::new(
+                self.root.as_ref(),
+                &self.normalize(pref),
+                max_dist,
+                self.len(),
+            ),
+            form: self.form,
+        }
+    }
+
+    /// Method returns iterator over all keys within Damerau-Levenshtein distance `max_dist`
+    /// of `query` in sorted order. Unlike plain Levenshtein distance, a swap of two adjacent
+    /// characters counts as one edit (each character takes part in one swap at most).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("the", 1);
+    /// m.insert("then", 2);
+    /// m.insert("tea", 3);
+    ///
+    /// let keys: Vec<String> = m.damerau_iter("teh", 1).map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["tea", "the"], keys);
+    /// assert_eq!(vec![("the".to_string(), &1)], m.damerau_iter("the", 0).collect::<Vec<_>>());
+    /// ```
+    pub fn damerau_iter(&self, query: &str, max_dist: usize) -> DamerauIter<'_, Value> {
+        DamerauIter {
+            iter: DamerauTraverse::new(
+                self.root.as_ref(),
+                &self.normalize(query),
+                max_dist,
+                self.len(),
+            ),
+            form: self.form,
+        }
+    }
+
+    /// Method returns mutable iterator over all values with common prefix `pref` in the `TSTMap`.
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    /// m.insert("abce", 1);
+    /// m.insert("abca", 1);
+    /// m.insert("zxd", 1);
+    /// m.insert("add", 1);
+    /// m.insert("abcdef", 1);
+    ///
+    /// for (key, value) in m.prefix_iter_mut("abc") {
+    ///     *value += 100;
+    /// }
+    /// assert_eq!(101, m["abc"]);
+    /// assert_eq!(101, m["abcdef"]);
+    /// ```
+    pub fn prefix_iter_mut(&mut self, pref: &str) -> IterMut<Value> {
+        let pref = self.normalize(pref);
+        let node = traverse::search(self.root.as_ref(), &pref, self.char_cmp());
+        IterMut::with_prefix(node, &pref, self.form)
+    }
+
+    /// Gets an iterator over the entries of the TSTMap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("bbc", 2);
+    /// m.insert("cccda", 3);
+    ///
+    /// for (key, value) in m.iter() {
+    ///     println!("{}: {}", key, value);
+    /// }
+    ///
+    /// let (first_key, first_value) = m.iter().next().unwrap();
+    /// assert_eq!((first_key, *first_value), ("abc".to_string(), 1));
+    /// ```
+    pub fn iter(&self) -> Iter<Value> {
+        Iter::new(self.root.as_ref(), self.form)
+    }
+
+    /// Gets an iterator over the entries of the TSTMap with keys not smaller than
+    /// `start`, in the order of keys. It starts without visiting the smaller keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("bbc", 2);
+    /// m.insert("cccda", 3);
+    ///
+    /// let page: Vec<_> = m.iter_from("abd").collect();
+    /// assert_eq!(vec![("bbc".to_string(), &2), ("cccda".to_string(), &3)], page);
+    /// assert_eq!(3, m.iter_from("abc").len());
+    /// ```
+    pub fn iter_from(&self, start: &str) -> Iter<'_, Value> {
+        Iter::from_key(self.root.as_ref(), &self.normalize(start), self.form)
+    }
+
+    /// Gets a mutable iterator over the entries of the `TSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    ///
+    /// for (key, value) in m.iter_mut() {
+    ///     if key != "a" {
+    ///         *value += 10;
+    ///     }
+    /// }
+    /// assert_eq!(1, m["a"]);
+    /// assert_eq!(12, m["b"]);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<Value> {
+        IterMut::new(self.root.as_ref_mut(), self.form)
+    }
+
+    /// Calls `f` for the entries of the `TSTMap` in sorted order of keys, until it returns
+    /// `ControlFlow::Break`. Unlike iterators, the key is built in one buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    ///
+    /// let mut keys = vec![];
+    /// m.walk(|key, value| {
+    ///     if *value > 2 {
+    ///         return ControlFlow::Break(());
+    ///     }
+    ///     keys.push(key.to_string());
+    ///     ControlFlow::Continue(())
+    /// });
+    /// assert_eq!(vec!["a", "b"], keys);
+    /// ```
+    pub fn walk<F>(&self, mut f: F)
+    where
+        F: FnMut(&str, &Value) -> ControlFlow<()>,
+    {
+        let form = self.form;
+        let _ = traverse::try_visit_nodes(self.root.as_ref(), |key, node, _| {
+            f(
+                &form.orient(Cow::Borrowed(key)),
+                node.value.as_ref().unwrap(),
+            )
+        });
+    }
+
+    /// Gets an iterator over the entries of the `TSTMap` with keys as filesystem paths,
+    /// sorted by keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert_path(Path::new("/usr/lib"), 2);
+    /// m.insert_path(Path::new("/usr/bin"), 1);
+    ///
+    /// let paths: Vec<PathBuf> = m.path_iter().map(|(p, _)| p).collect();
+    /// assert_eq!(vec![PathBuf::from("/usr/bin"), PathBuf::from("/usr/lib")], paths);
+    /// ```
+    pub fn path_iter(&self) -> PathIter<'_, Value> {
+        PathIter { iter: self.iter() }
+    }
+
+    /// An iterator visiting all keys in arbitrary order.
+    /// Iterator element type is String
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    ///
+    /// for key in m.keys() {
+    ///     println!("{}", key);
+    /// }
+    ///
+    /// assert_eq!(3, m.keys().len());
+    /// assert_eq!(vec!["c", "b", "a"], m.keys().rev().collect::<Vec<_>>());
+    /// ```
+    pub fn keys(&self) -> KeysIter<Value> {
+        KeysIter {
+            iter: self.iter().map(first),
+        }
+    }
+
+    /// An iterator visiting all keys with common prefix `pref` in sorted order.
+    /// Iterator element type is String
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    /// m.insert("b", 3);
+    ///
+    /// let keys: Vec<String> = m.prefix_keys("ab").collect();
+    /// assert_eq!(vec!["abc", "abd"], keys);
+    /// ```
+    pub fn prefix_keys(&self, pref: &str) -> KeysIter<'_, Value> {
+        KeysIter {
+            iter: self.prefix_iter(pref).map(first),
+        }
+    }
+
+    /// An iterator visiting all keys exactly `n` characters long in sorted order.
+    /// Length is counted in chars, not bytes.
+    /// Iterator element type is String
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("crane", 1);
+    /// m.insert("cran", 2);
+    /// m.insert("cranes", 3);
+    /// m.insert("слово", 4);
+    ///
+    /// let keys: Vec<String> = m.keys_of_length(5).collect();
+    /// assert_eq!(vec!["crane", "слово"], keys);
+    /// ```
+    pub fn keys_of_length(&self, n: usize) -> KeysOfLengthIter<'_, Value> {
+        KeysOfLengthIter {
+            iter: WildCardTraverse::with_length(self.root.as_ref(), n, self.len()),
+            form: self.form,
+        }
+    }
+
+    /// An iterator visiting all values in arbitrary order.
+    /// Iterator element type is &V
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    ///
+    /// for value in m.values() {
+    ///     println!("{}", value);
+    /// }
+    ///
+    /// assert_eq!(vec![&3, &2, &1], m.values().rev().collect::<Vec<_>>());
+    /// ```
+    pub fn values(&self) -> ValuesIter<Value> {
+        ValuesIter {
+            iter: ValuesTraverse::new(self.root.as_ref()),
+        }
+    }
+
+    /// Returns all entries of the `TSTMap` sorted by keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// assert_eq!(vec![("a".to_string(), &1), ("b".to_string(), &2)], m.to_sorted_vec());
+    /// ```
+    pub fn to_sorted_vec(&self) -> Vec<(String, &Value)> {
+        let mut vec = Vec::with_capacity(self.len());
+        vec.extend(self.iter());
+        vec
+    }
+
+    /// Consumes the `TSTMap` and returns all its entries sorted by keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 2);
+    /// m.insert("a", 1);
+    ///
+    /// assert_eq!(vec![("a".to_string(), 1), ("b".to_string(), 2)], m.into_sorted_vec());
+    /// ```
+    pub fn into_sorted_vec(self) -> Vec<(String, Value)> {
+        let mut vec = Vec::with_capacity(self.len());
+        vec.extend(self);
+        vec
+    }
+
+    /// Returns all entries of the `TSTMap` sorted by values with `compare` function.
+    /// Entries with equal values stay sorted by keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 2);
+    /// m.insert("b", 3);
+    /// m.insert("c", 2);
+    ///
+    /// let top = m.entries_sorted_by(|a, b| b.cmp(a));
+    /// assert_eq!(vec![("b".to_string(), &3), ("a".to_string(), &2), ("c".to_string(), &2)], top);
+    /// ```
+    pub fn entries_sorted_by<F>(&self, mut compare: F) -> Vec<(String, &Value)>
+    where
+        F: FnMut(&Value, &Value) -> Ordering,
+    {
+        let mut entries: Vec<(String, &Value)> = self.iter().collect();
+        // sort is stable, so ties keep the order of keys
+        entries.sort_by(|a, b| compare(a.1, b.1));
+        entries
+    }
+
+    /// Returns up to `limit` keys within Levenshtein distance `max_dist` of `query`,
+    /// together with their distance. Keys are ordered by distance, then alphabetically.
+    ///
+    /// Branches of the tree are pruned as soon as no key below them can be close enough.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("apple", 1);
+    /// m.insert("apply", 2);
+    /// m.insert("maple", 3);
+    /// m.insert("ample", 4);
+    ///
+    /// assert_eq!(
+    ///     vec![("apple".to_string(), 1), ("apply".to_string(), 1)],
+    ///     m.suggest("appla", 1, 5)
+    /// );
+    /// assert_eq!(vec![("apple".to_string(), 0)], m.suggest("apple", 2, 1));
+    /// ```
+    pub fn suggest(&self, query: &str, max_dist: usize, limit: usize) -> Vec<(String, usize)> {
+        if limit == 0 {
+            return Vec::new();
+        }
+        let mut heap: BinaryHeap<(usize, String)> = BinaryHeap::with_capacity(limit + 1);
+        traverse::fuzzy(
+            self.root.as_ref(),
+            &self.normalize(query),
+            max_dist,
+            |key, _, dist| {
+                let key = self.form.orient(Cow::Borrowed(key));
+                let key = key.as_ref();
+                if heap.len() == limit {
+                    match heap.peek() {
+                        Some((worst, worst_key)) if (dist, key) >= (*worst, worst_key.as_str()) => {
+                            return
+                        }
+                        _ => {}
+                    }
+                }
+                heap.push((dist, key.to_string()));
+                if heap.len() > limit {
+                    heap.pop();
+                }
+            },
+        );
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|(dist, key)| (key, dist))
+            .collect()
+    }
+
+    /// Calls `f` with key, value and depth of the node for each element of the `TSTMap`
+    /// in sorted order.
+    ///
+    /// Depth is the number of nodes on the path from the root to the node holding
+    /// the value, so it is never smaller than the length of the key.
+    /// The key is passed as a borrowed buffer, no `String` is allocated per element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 1);
+    /// m.insert("a", 2);
+    /// m.insert("ab", 3);
+    ///
+    /// let mut visited = vec![];
+    /// m.visit(|key, value, depth| visited.push((key.to_string(), *value, depth)));
+    /// assert_eq!(
+    ///     vec![("a".to_string(), 2, 2), ("ab".to_string(), 3, 3), ("b".to_string(), 1, 1)],
+    ///     visited
+    /// );
+    /// ```
+    pub fn visit<F: FnMut(&str, &Value, usize)>(&self, mut f: F) {
+        let form = self.form;
+        traverse::visit(self.root.as_ref(), |key, value, depth| {
+            f(&form.orient(Cow::Borrowed(key)), value, depth)
+        })
+    }
+
+    /// Calls `f` with key and value for each element of the `TSTMap` in sorted order.
+    ///
+    /// The key is borrowed from a buffer reused for all elements, so unlike `iter` no
+    /// `String` is allocated per element. The key can't be kept past the call of `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 1);
+    /// m.insert("a", 2);
+    ///
+    /// let mut out = String::new();
+    /// m.for_each_ref(|key, value| out.push_str(&format!("{}={};", key, value)));
+    /// assert_eq!("a=2;b=1;", out);
+    /// ```
+    pub fn for_each_ref<'x, F: FnMut(&str, &'x Value)>(&'x self, mut f: F) {
+        let reversed = self.form.reversed;
+        // keys stored reversed are turned back in a single buffer as well
+        let mut buf = String::new();
+        traverse::visit(self.root.as_ref(), |key, value, _| {
+            if !reversed {
+                return f(key, value);
+            }
+            buf.clear();
+            buf.extend(key.chars().rev());
+            f(&buf, value)
+        })
+    }
+
+    /// Calls `f` with key and value for each element of the `TSTMap` in sorted order,
+    /// the same as `for_each_ref`. The key is borrowed from a reused buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("ab", 1);
+    /// m.insert("abc", 2);
+    ///
+    /// let mut total_len = 0;
+    /// m.for_each(|key, value| total_len += key.len() * value);
+    /// assert_eq!(8, total_len);
+    /// ```
+    pub fn for_each<F: FnMut(&str, &Value)>(&self, f: F) {
+        self.for_each_ref(f)
+    }
+
+    /// Method returns the longest prefix shared by all keys in the `TSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("/usr/lib/a", 1);
+    /// m.insert("/usr/lib/b", 1);
+    /// m.insert("/usr/local", 1);
+    ///
+    /// assert_eq!("/usr/l", m.common_prefix());
+    /// m.insert("/usr", 1);
+    /// assert_eq!("/usr", m.common_prefix());
+    /// m.insert("tmp", 1);
+    /// assert_eq!("", m.common_prefix());
+    /// ```
+    pub fn common_prefix(&self) -> String {
+        let prefix = traverse::common_prefix(self.root.as_ref());
+        self.form.orient(prefix.into()).into_owned()
+    }
+
+    /// Collects statistics about the shape of the tree in one traversal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 1);
+    /// m.insert("a", 2);
+    /// m.insert("bc", 3);
+    ///
+    /// let stats = m.stats();
+    /// assert_eq!(3, stats.nodes);
+    /// assert_eq!(3, stats.values);
+    /// assert_eq!(2, stats.max_depth);
+    /// assert_eq!([2, 0, 1, 0], stats.children);
+    /// ```
+    pub fn stats(&self) -> TSTStats {
+        let mut stats = TSTStats::default();
+        let mut key_len_sum = 0;
+        traverse::walk_nodes(self.root.as_ref(), |node, depth, len| {
+            stats.nodes += 1;
+            stats.max_depth = stats.max_depth.max(depth);
+            let children = [&node.lt, &node.eq, &node.gt]
+                .iter()
+                .filter(|child| child.is_some())
+                .count();
+            stats.children[children] += 1;
+            if node.value.is_some() {
+                stats.values += 1;
+                key_len_sum += len;
+            }
+        });
+        if stats.values > 0 {
+            stats.avg_key_len = key_len_sum as f64 / stats.values as f64;
+        }
+        stats
+    }
+
+    /// Checks the internal structure of the `TSTMap`: chars of nodes are ordered,
+    /// the number of values in every subtree is counted right and the number of
+    /// all values equals `len`. Returns a description of the first problem found.
+    ///
+    /// It visits every node, so it's meant for tests rather than regular use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abd", 2);
+    /// m.remove("abc");
+    /// assert_eq!(Ok(()), m.validate());
+    /// ```
+    pub fn validate(&self) -> Result<(), String> {
+        let count = traverse::validate(self.root.as_ref(), self.char_cmp())?;
+        if count != self.size {
+            return Err(format!("{} values, but len is {}", count, self.size));
+        }
+        Ok(())
+    }
+
+    /// Returns sorted distinct chars used in keys of the `TSTMap`.
+    /// Every node is visited once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("cab", 1);
+    /// m.insert("abba", 2);
+    ///
+    /// assert_eq!(vec!['a', 'b', 'c'], m.alphabet());
+    /// ```
+    pub fn alphabet(&self) -> Vec<char> {
+        traverse::alphabet(self.root.as_ref())
+    }
+
+    /// Returns true if keys of the `TSTMap` use only chars from `allowed`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("cab", 1);
+    /// m.insert("abba", 2);
+    ///
+    /// assert!(m.uses_only(&['a', 'b', 'c', 'd']));
+    /// assert!(!m.uses_only(&['a', 'b']));
+    /// ```
+    pub fn uses_only(&self, allowed: &[char]) -> bool {
+        self.alphabet().iter().all(|c| allowed.contains(c))
+    }
+
+    /// Returns the smallest key of the `TSTMap` with its value, without removing them.
+    /// Unlike iterators, it doesn't build keys of other entries on the way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 1);
+    /// m.insert("ab", 2);
+    /// m.insert("abc", 3);
+    ///
+    /// assert_eq!(Some(("ab".to_string(), &2)), m.peek_min());
+    /// assert_eq!(None, TSTMap::<i32>::new().peek_min());
+    /// ```
+    pub fn peek_min(&self) -> Option<(String, &Value)> {
+        traverse::first_entry(self.root.as_ref(), false).map(|entry| self.form.restore(entry))
+    }
+
+    /// Returns the largest key of the `TSTMap` with its value, without removing them.
+    /// Unlike iterators, it doesn't build keys of other entries on the way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("bc", 3);
+    ///
+    /// assert_eq!(Some(("bc".to_string(), &3)), m.peek_max());
+    /// assert_eq!(None, TSTMap::<i32>::new().peek_max());
+    /// ```
+    pub fn peek_max(&self) -> Option<(String, &Value)> {
+        traverse::first_entry(self.root.as_ref(), true).map(|entry| self.form.restore(entry))
+    }
+}
+
+impl<'x, Value: 'x> TSTMap<Value> {
+    /// Method returns longest prefix `pref` in the `TSTMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    /// m.insert("abce", 1);
+    /// m.insert("abca", 1);
+    /// m.insert("zxd", 1);
+    /// m.insert("add", 1);
+    /// m.insert("abcdef", 1);
+    ///
+    /// assert_eq!("abcd", m.longest_prefix("abcde"));
+    /// ```
+    pub fn longest_prefix(&self, pref: &'x str) -> &'x str {
+        &pref[self.longest_prefix_range(pref, self.form)]
+    }
+
+    // byte range of the longest prefix of `query`, which is a key in `form`,
+    // or of the longest suffix for reversed keys
+    fn longest_prefix_range(&self, query: &str, form: KeyForm) -> Range<usize> {
+        if form.reversed || form.changes(query).is_some() {
+            let end = if form.reversed { query.len() } else { 0 };
+            let last = self.prefix_values(query, form).pop();
+            return last.map_or(end..end, |(range, _)| range);
+        }
+        let len = traverse::longest_prefix_len(self.root.as_ref(), query.chars(), self.char_cmp());
+        let end = query
+            .char_indices()
+            .nth(len)
+            .map_or(query.len(), |(end, _)| end);
+        0..end
+    }
+
+    // byte ranges of prefixes of `query`, which are keys in `form`, or of suffixes
+    // for reversed keys, with their values, shortest first
+    fn prefix_values<'v>(&'v self, query: &str, form: KeyForm) -> Vec<(Range<usize>, &'v Value)> {
+        let len = query.len();
+        let range = |n: usize| if form.reversed { len - n..len } else { 0..n };
+        let cmp = self.char_cmp();
+        if form.changes(query).is_none() {
+            let oriented = form.orient(Cow::Borrowed(query));
+            let found = traverse::prefix_values(self.root.as_ref(), &oriented, cmp);
+            return found
+                .into_iter()
+                .map(|(n, value)| (range(n), value))
+                .collect();
+        }
+        // a part may be brought to another form than the same part of the whole `query`
+        let lens: Vec<usize> = if form.reversed {
+            query
+                .char_indices()
+                .rev()
+                .map(|(start, _)| len - start)
+                .collect()
+        } else {
+            query
+                .char_indices()
+                .map(|(start, c)| start + c.len_utf8())
+                .collect()
+        };
+        lens.into_iter()
+            .filter_map(|n| {
+                let key = form.apply(&query[range(n)]);
+                let node = traverse::search(self.root.as_ref(), &key, cmp)?;
+                node.value.as_ref().map(|value| (range(n), value))
+            })
+            .collect()
+    }
+
+    /// Method returns iterator over all keys in the `TSTMap`, which are prefixes of `query`,
+    /// from the longest to the shortest, e.g. to try the most specific route first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("/", 1);
+    /// m.insert("/api", 2);
+    /// m.insert("/api/users", 3);
+    /// m.insert("/static", 4);
+    ///
+    /// let routes: Vec<_> = m.matching_prefixes_longest_first("/api/users/42").collect();
+    /// assert_eq!(vec![("/api/users", &3), ("/api", &2), ("/", &1)], routes);
+    /// assert_eq!(0, m.matching_prefixes_longest_first("api").count());
+    /// ```
+    pub fn matching_prefixes_longest_first(
+        &'x self,
+        query: &'x str,
+    ) -> PrefixMatchesIter<'x, Value> {
+        PrefixMatchesIter {
+            query,
+            iter: self.prefix_values(query, self.form).into_iter().rev(),
+        }
+    }
+
+    /// Method returns the longest suffix of `query`, which is a key of a `TSTMap` created
+    /// by `new_reversed` or was inserted with `insert_reversed`. Keys of such a `TSTMap`
+    /// are stored reversed, so the suffix is looked up as a prefix of reversed `query`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new_reversed();
+    /// m.insert("uk", 1);
+    /// m.insert("co.uk", 2);
+    ///
+    /// assert_eq!("co.uk", m.longest_suffix("bbc.co.uk"));
+    /// assert_eq!("uk", m.longest_suffix("gov.uk"));
+    /// assert_eq!("", m.longest_suffix("example.com"));
+    /// assert_eq!(Some(&2), m.get("co.uk"));
+    /// ```
+    pub fn longest_suffix(&self, query: &'x str) -> &'x str {
+        let form = KeyForm {
+            reversed: true,
+            ..self.form
+        };
+        &query[self.longest_prefix_range(query, form)]
+    }
+
+    /// Method returns the length in chars of the longest key in the `TSTMap`, which is
+    /// a prefix of `query`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// m.insert("abcd", 1);
+    ///
+    /// assert_eq!(4, m.longest_prefix_len("abcde".chars()));
+    /// assert_eq!(3, m.longest_prefix_len(vec!['a', 'b', 'c']));
+    /// assert_eq!(0, m.longest_prefix_len(['a', 'b'].iter().copied()));
+    /// ```
+    pub fn longest_prefix_len<I: IntoIterator<Item = char>>(&self, query: I) -> usize {
+        if !self.form.is_identity() {
+            let query: String = query.into_iter().collect();
+            return query[self.longest_prefix_range(&query, self.form)]
+                .chars()
+                .count();
+        }
+        traverse::longest_prefix_len(self.root.as_ref(), query, self.char_cmp())
+    }
+
+    /// Returns the length in chars of the longest common prefix of `a` and `b`,
+    /// which is a path in the `TSTMap`, i.e. a prefix of some stored key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abcd", 1);
+    /// m.insert("abef", 2);
+    ///
+    /// assert_eq!(3, m.divergence_point("abcx", "abcy"));
+    /// assert_eq!(2, m.divergence_point("abxy", "abxz"));
+    /// assert_eq!(0, m.divergence_point("x", "x"));
+    /// ```
+    pub fn divergence_point(&self, a: &str, b: &str) -> usize {
+        let (a, b) = (self.normalize(a), self.normalize(b));
+        traverse::divergence_point(self.root.as_ref(), &a, &b, self.char_cmp())
+    }
+
+    /// Returns the number of keys starting with the common prefix of `a` and `b`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("abcd", 1);
+    /// m.insert("abce", 2);
+    /// m.insert("abf", 3);
+    ///
+    /// assert_eq!(2, m.keys_between_prefix("abcd", "abcx"));
+    /// assert_eq!(3, m.keys_between_prefix("abcd", "abf"));
+    /// assert_eq!(0, m.keys_between_prefix("abx", "abxy"));
+    /// assert_eq!(3, m.keys_between_prefix("x", "y"));
+    /// ```
+    pub fn keys_between_prefix(&self, a: &str, b: &str) -> usize {
+        let (a, b) = (self.normalize(a), self.normalize(b));
+        let common = a
+            .chars()
+            .zip(b.chars())
+            .take_while(|(x, y)| x == y)
+            .map(|(x, _)| x.len_utf8())
+            .sum();
+        if common == 0 {
+            return self.len();
+        }
+        // the keys are already in the form they are stored in
+        traverse::count_prefix(self.root.as_ref(), &a[..common], self.char_cmp())
+    }
+}
+
+impl<Value: Ord> TSTMap<Value> {
+    /// Returns all entries of the `TSTMap` sorted by values.
+    /// Entries with equal values stay sorted by keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 2);
+    /// m.insert("b", 1);
+    /// m.insert("c", 2);
+    ///
+    /// let sorted = m.entries_sorted_by_value();
+    /// assert_eq!(vec![("b".to_string(), &1), ("a".to_string(), &2), ("c".to_string(), &2)], sorted);
+    /// ```
+    pub fn entries_sorted_by_value(&self) -> Vec<(String, &Value)> {
+        self.entries_sorted_by(Ord::cmp)
     }
+}
 
-    /// Returns true if the `TSTMap` contains no elements.
+impl<Value: Clone> TSTMap<Value> {
+    /// Returns a new `TSTMap` with clones of the entries, which keys match the glob
+    /// pattern `pat`. Besides the syntax of `wildcard_iter`, `*` matches any sequence
+    /// of characters, including the empty one.
     ///
     /// # Examples
     ///
@@ -242,37 +2733,69 @@ impl<Value> TSTMap<Value> {
     /// use tst::TSTMap;
     ///
     /// let mut m = TSTMap::new();
-    /// assert!(m.is_empty());
+    /// m.insert("net.http.port", 80);
+    /// m.insert("net.http.timeout", 30);
+    /// m.insert("net.smtp.port", 25);
+    /// m.insert("log.level", 1);
     ///
-    /// m.insert("abc", 1);
-    /// assert!(!m.is_empty());
+    /// let ports = m.matching("net*port");
+    /// assert_eq!(vec!["net.http.port", "net.smtp.port"], ports.keys().collect::<Vec<_>>());
+    /// assert_eq!(Some(&25), ports.get("net.smtp.port"));
+    /// assert_eq!(3, m.matching("net.*").len());
+    /// assert_eq!(1, m.matching("log.level").len());
     /// ```
-    #[inline]
-    pub fn is_empty(&self) -> bool {
-        self.size == 0
+    pub fn matching(&self, pat: &str) -> TSTMap<Value> {
+        let form = self.form;
+        let mut map = TSTMap::new();
+        map.form = form;
+        let mut iter = WildCardTraverse::glob(
+            self.root.as_ref(),
+            pat,
+            form.reversed,
+            self.len(),
+            form.cmp(),
+        );
+        while let Some((key, value)) = iter.next() {
+            let key = form.orient(key.into());
+            // a key may match the pattern in more than one way
+            if !map.contains_key(&key) {
+                map.insert(key, value.clone());
+            }
+        }
+        map
     }
+}
 
-    /// Clears the `TSTMap`.
+impl<Value: ops::AddAssign + From<u8> + Default + Clone> TSTMap<Value> {
+    /// Adds one to the counter at `key`, starting from zero if the key is absent,
+    /// and returns the new count.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is empty.
     ///
     /// # Examples
     ///
     /// ```
     /// use tst::TSTMap;
     ///
-    /// let mut m = TSTMap::new();
-    /// m.insert("abc", 1);
-    /// m.insert("abd", 100);
-    /// m.clear();
-    ///
-    /// assert!(m.is_empty());
-    /// assert_eq!(None, m.get("abc"));
+    /// let mut m = TSTMap::<u64>::new();
+    /// for word in "a b a".split(' ') {
+    ///     m.increment(word);
+    /// }
+    /// assert_eq!(3, m.increment("a"));
+    /// assert_eq!(1, m["b"]);
     /// ```
-    pub fn clear(&mut self) {
-        *self = TSTMap::<Value>::new();
+    pub fn increment<Key: AsRef<str>>(&mut self, key: Key) -> Value {
+        self.bump_by(key, Value::from(1))
     }
 
-    /// An iterator returning all nodes matching wildcard pattern `pat`.
-    /// Iterator element type is (String, V)
+    /// Adds `n` to the counter at `key`, starting from zero if the key is absent,
+    /// and returns the new count.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is empty.
     ///
     /// # Examples
     ///
@@ -280,92 +2803,128 @@ impl<Value> TSTMap<Value> {
     /// use tst::TSTMap;
     ///
     /// let mut m = TSTMap::new();
-    /// m.insert("a", 1);
-    /// m.insert("b", 2);
-    /// m.insert("c", 3);
-    ///
-    /// for (k, v) in m.wildcard_iter(".") {
-    ///     println!("{} -> {}", k, v);
-    /// }
+    /// assert_eq!(2.5, m.bump_by("a", 2.5));
+    /// assert_eq!(3.0, m.bump_by("a", 0.5));
     /// ```
-    pub fn wildcard_iter(&self, pat: &str) -> WildCardIter<Value> {
-        WildCardIter::new(self.root.as_ref(), pat, self.len())
+    pub fn bump_by<Key: AsRef<str>>(&mut self, key: Key, n: Value) -> Value {
+        let value = self.entry(key.as_ref()).or_insert_with(Default::default);
+        *value += n;
+        value.clone()
     }
 
-    /// An mutable iterator returning all nodes matching wildcard pattern `pat`.
+    /// Sums counters by the first `len` chars of their keys and returns the sums in the
+    /// order of these prefixes. Keys shorter than `len` chars are summed by themselves.
     ///
     /// # Examples
     ///
     /// ```
     /// use tst::TSTMap;
     ///
-    /// let mut m = TSTMap::new();
-    /// m.insert("a", 1);
-    /// m.insert("b", 2);
-    /// m.insert("c", 3);
+    /// let mut m = TSTMap::<u64>::new();
+    /// m.bump_by("applepie", 2);
+    /// m.bump_by("applejack", 3);
+    /// m.bump_by("apricot", 1);
+    /// m.bump_by("ap", 4);
     ///
-    /// for (k, v) in m.wildcard_iter_mut(".") {
-    ///     *v += 10;
-    /// }
-    /// assert_eq!(11, m["a"]);
-    /// assert_eq!(12, m["b"]);
-    /// assert_eq!(13, m["c"]);
+    /// assert_eq!(
+    ///     vec![("ap".to_string(), 4), ("appl".to_string(), 5), ("apri".to_string(), 1)],
+    ///     m.count_prefixes_of_length(4)
+    /// );
     /// ```
-    pub fn wildcard_iter_mut(&mut self, pat: &str) -> WildCardIterMut<Value> {
-        WildCardIterMut::new(self.root.as_ref_mut(), pat, self.len())
+    pub fn count_prefixes_of_length(&self, len: usize) -> Vec<(String, Value)> {
+        let mut counts: Vec<(String, Value)> = vec![];
+        // keys in the form they are stored in, which they are sorted by
+        let mut iter = Traverse::new(self.root.as_ref());
+        while let Some((mut key, count)) = iter.next() {
+            if let Some((end, _)) = key.char_indices().nth(len) {
+                key.truncate(end);
+            }
+            // keys with the same prefix go one after another in sorted order
+            match counts.last_mut() {
+                Some((prefix, sum)) if *prefix == key => *sum += count.clone(),
+                _ => counts.push((key, count.clone())),
+            }
+        }
+        counts
+            .into_iter()
+            .map(|entry| self.form.restore(entry))
+            .collect()
     }
+}
 
-    /// Method returns iterator over all values with common prefix `pref` in the `TSTMap`.
+impl<Value> TSTMap<Value> {
+    /// Writes the `TSTMap` to `w` in the binary format of `write_to`, but with values
+    /// encoded by `codec`. A `TSTMap` with a custom order of chars or reversed keys
+    /// is written in code point order with keys as given, which `load` reads it back in.
+    ///
     /// # Examples
     ///
     /// ```
-    /// use tst::TSTMap;
-    /// let mut m = TSTMap::new();
-    /// m.insert("abc", 1);
-    /// m.insert("abcd", 1);
-    /// m.insert("abce", 1);
-    /// m.insert("abca", 1);
-    /// m.insert("zxd", 1);
-    /// m.insert("add", 1);
-    /// m.insert("abcdef", 1);
+    /// use tst::{BinaryCodec, TSTMap};
     ///
-    /// for (key, value) in m.prefix_iter("abc") {
-    ///     println!("{}: {}", key, value);
-    /// }
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1u32);
     ///
+    /// let mut blob = vec![];
+    /// m.save(&mut blob, &BinaryCodec).unwrap();
+    /// assert_eq!(m, TSTMap::load(&mut &blob[..], &BinaryCodec).unwrap());
     /// ```
-    pub fn prefix_iter(&self, pref: &str) -> Iter<Value> {
-        let node = traverse::search(self.root.as_ref(), pref);
-        Iter::with_prefix(node, pref, self.len())
+    pub fn save<C: ValueCodec<Value>, W: Write>(&self, w: &mut W, codec: &C) -> io::Result<()> {
+        if self.form.char_order.is_some() || self.form.reversed {
+            // the format has no room for the order and the reversal,
+            // so rebuild the tree with keys as given in code point order
+            let mut plain = TSTMap::new();
+            for (key, value) in self.iter() {
+                plain.insert(&key, value);
+            }
+            return binary::write_tree(plain.root.as_ref(), plain.size, w, |value, w| {
+                codec.encode(*value, w)
+            });
+        }
+        binary::write_tree(self.root.as_ref(), self.size, w, |value, w| {
+            codec.encode(value, w)
+        })
     }
 
-    /// Method returns mutable iterator over all values with common prefix `pref` in the `TSTMap`.
+    /// Reads a `TSTMap` written by `save` from `r`, decoding values with `codec`,
+    /// which has to be the same as the one it was saved with.
+    /// Returns an error of kind `InvalidData` if `r` doesn't contain a valid `TSTMap`
+    /// binary of a supported version.
+    pub fn load<C: ValueCodec<Value>, R: Read>(r: &mut R, codec: &C) -> io::Result<Self> {
+        let mut map = TSTMap::new();
+        map.size = binary::read_tree(map.root.as_mut(), &mut map.pool, r, |r| codec.decode(r))?;
+        #[cfg(feature = "insertion-order")]
+        {
+            map.next_seq = map.size;
+        }
+        Ok(map)
+    }
+}
+
+impl<Value: BinaryValue> TSTMap<Value> {
+    /// Writes the `TSTMap` to `w` in a compact binary format, which `read_from`
+    /// loads back much faster than inserting all keys again.
+    ///
     /// # Examples
     ///
     /// ```
     /// use tst::TSTMap;
+    ///
     /// let mut m = TSTMap::new();
-    /// m.insert("abc", 1);
-    /// m.insert("abcd", 1);
-    /// m.insert("abce", 1);
-    /// m.insert("abca", 1);
-    /// m.insert("zxd", 1);
-    /// m.insert("add", 1);
-    /// m.insert("abcdef", 1);
+    /// m.insert("abc", 1u32);
+    /// m.insert("abd", 2);
     ///
-    /// for (key, value) in m.prefix_iter_mut("abc") {
-    ///     *value += 100;
-    /// }
-    /// assert_eq!(101, m["abc"]);
-    /// assert_eq!(101, m["abcdef"]);
+    /// let mut blob = vec![];
+    /// m.write_to(&mut blob).unwrap();
+    /// assert_eq!(m, TSTMap::read_from(&mut &blob[..]).unwrap());
     /// ```
-    pub fn prefix_iter_mut(&mut self, pref: &str) -> IterMut<Value> {
-        let len = self.len();
-        let node = traverse::search(self.root.as_ref(), pref);
-        IterMut::with_prefix(node, pref, len)
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.save(w, &BinaryCodec)
     }
 
-    /// Gets an iterator over the entries of the TSTMap.
+    /// Reads a `TSTMap` written by `write_to` from `r`.
+    /// Returns an error of kind `InvalidData` if `r` doesn't contain a valid `TSTMap`
+    /// binary of a supported version.
     ///
     /// # Examples
     ///
@@ -373,23 +2932,23 @@ impl<Value> TSTMap<Value> {
     /// use tst::TSTMap;
     ///
     /// let mut m = TSTMap::new();
-    /// m.insert("abc", 1);
-    /// m.insert("bbc", 2);
-    /// m.insert("cccda", 3);
+    /// m.insert("abc", "first".to_string());
     ///
-    /// for (key, value) in m.iter() {
-    ///     println!("{}: {}", key, value);
-    /// }
+    /// let mut blob = vec![];
+    /// m.write_to(&mut blob).unwrap();
+    /// let loaded: TSTMap<String> = TSTMap::read_from(&mut &blob[..]).unwrap();
+    /// assert_eq!("first", loaded["abc"]);
     ///
-    /// let (first_key, first_value) = m.iter().next().unwrap();
-    /// assert_eq!((first_key, *first_value), ("abc".to_string(), 1));
+    /// assert!(TSTMap::<String>::read_from(&mut &b"garbage"[..]).is_err());
     /// ```
-    pub fn iter(&self) -> Iter<Value> {
-        let len = self.len();
-        Iter::new(self.root.as_ref(), len, len)
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        Self::load(r, &BinaryCodec)
     }
+}
 
-    /// Gets a mutable iterator over the entries of the `TSTMap`.
+impl<Value: PartialEq> TSTMap<Value> {
+    /// Returns true if any key of the `TSTMap` maps to `value`.
+    /// This is a linear scan over all values, so it takes O(n).
     ///
     /// # Examples
     ///
@@ -397,25 +2956,18 @@ impl<Value> TSTMap<Value> {
     /// use tst::TSTMap;
     ///
     /// let mut m = TSTMap::new();
-    /// m.insert("a", 1);
-    /// m.insert("b", 2);
-    /// m.insert("c", 3);
+    /// m.insert("200", "OK");
+    /// m.insert("404", "Not Found");
     ///
-    /// for (key, value) in m.iter_mut() {
-    ///     if key != "a" {
-    ///         *value += 10;
-    ///     }
-    /// }
-    /// assert_eq!(1, m["a"]);
-    /// assert_eq!(12, m["b"]);
+    /// assert!(m.contains_value(&"OK"));
+    /// assert!(!m.contains_value(&"Teapot"));
     /// ```
-    pub fn iter_mut(&mut self) -> IterMut<Value> {
-        let len = self.len();
-        IterMut::new(self.root.as_ref_mut(), len, len)
+    pub fn contains_value(&self, value: &Value) -> bool {
+        self.values().any(|v| v == value)
     }
 
-    /// An iterator visiting all keys in arbitrary order.
-    /// Iterator element type is String
+    /// Returns the first key in sorted order, which maps to `value`.
+    /// This is a linear scan over the `TSTMap`, so it takes O(n).
     ///
     /// # Examples
     ///
@@ -423,25 +2975,24 @@ impl<Value> TSTMap<Value> {
     /// use tst::TSTMap;
     ///
     /// let mut m = TSTMap::new();
-    /// m.insert("a", 1);
-    /// m.insert("b", 2);
-    /// m.insert("c", 3);
+    /// m.insert("404", "Not Found");
+    /// m.insert("200", "OK");
+    /// m.insert("204", "OK");
     ///
-    /// for key in m.keys() {
-    ///     println!("{}", key);
-    /// }
+    /// assert_eq!(Some("200".to_string()), m.find_key(&"OK"));
+    /// assert_eq!(None, m.find_key(&"Teapot"));
     /// ```
-    pub fn keys(&self) -> KeysIter<Value> {
-        fn first<A, B>((k, _): (A, B)) -> A {
-            k
-        }
-        KeysIter {
-            iter: self.iter().map(first),
-        }
+    pub fn find_key(&self, value: &Value) -> Option<String> {
+        self.iter().find(|(_, v)| *v == value).map(first)
     }
+}
 
-    /// An iterator visiting all values in arbitrary order.
-    /// Iterator element type is &V
+#[cfg(feature = "insertion-order")]
+impl<Value> TSTMap<Value> {
+    /// Gets an iterator over the entries of the `TSTMap` in the order they were first
+    /// inserted. Replacing a value keeps its position, while a removed and inserted again
+    /// key moves to the end.
+    /// Available with the `insertion-order` feature.
     ///
     /// # Examples
     ///
@@ -449,41 +3000,48 @@ impl<Value> TSTMap<Value> {
     /// use tst::TSTMap;
     ///
     /// let mut m = TSTMap::new();
-    /// m.insert("a", 1);
-    /// m.insert("b", 2);
-    /// m.insert("c", 3);
+    /// m.insert("b", 1);
+    /// m.insert("c", 2);
+    /// m.insert("a", 3);
+    /// m.insert("b", 4);
     ///
-    /// for value in m.values() {
-    ///     println!("{}", value);
-    /// }
+    /// let keys: Vec<String> = m.iter_insertion_order().map(|(k, _)| k).collect();
+    /// assert_eq!(vec!["b", "c", "a"], keys);
     /// ```
-    pub fn values(&self) -> ValuesIter<Value> {
-        ValuesIter {
-            iter: ValuesTraverse::new(self.root.as_ref(), self.len(), self.len()),
+    pub fn iter_insertion_order(&self) -> InsertionOrderIter<'_, Value> {
+        InsertionOrderIter {
+            iter: traverse::insertion_order(self.root.as_ref()).into_iter(),
+            form: self.form,
         }
     }
 }
 
-impl<'x, Value: 'x> TSTMap<Value> {
-    /// Method returns longest prefix `pref` in the `TSTMap`.
+#[cfg(feature = "rayon")]
+impl<Value: Sync> TSTMap<Value> {
+    /// Gets a parallel iterator over the entries of the `TSTMap`.
+    /// Available with the `rayon` feature.
+    ///
+    /// The tree is split into subtrees, which are walked in parallel.
     ///
     /// # Examples
     ///
     /// ```
+    /// use rayon::prelude::*;
     /// use tst::TSTMap;
+    ///
     /// let mut m = TSTMap::new();
-    /// m.insert("abc", 1);
-    /// m.insert("abcd", 1);
-    /// m.insert("abce", 1);
-    /// m.insert("abca", 1);
-    /// m.insert("zxd", 1);
-    /// m.insert("add", 1);
-    /// m.insert("abcdef", 1);
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
     ///
-    /// assert_eq!("abcd", m.longest_prefix("abcde"));
+    /// assert_eq!(6, m.par_iter().map(|(_, v)| v).sum::<i32>());
     /// ```
-    pub fn longest_prefix(&self, pref: &'x str) -> &'x str {
-        traverse::longest_prefix(self.root.as_ref(), pref)
+    pub fn par_iter(&self) -> ParIter<'_, Value> {
+        let parts = rayon::current_num_threads() * 4;
+        ParIter {
+            parts: Traverse::split(self.root.as_ref(), parts),
+            form: self.form,
+        }
     }
 }
 
@@ -546,6 +3104,36 @@ impl<'x, Value> ops::IndexMut<&'x str> for TSTMap<Value> {
     }
 }
 
+impl<Value> ops::Index<String> for TSTMap<Value> {
+    type Output = Value;
+    #[inline]
+    fn index(&self, idx: String) -> &Value {
+        &self[idx.as_str()]
+    }
+}
+
+impl<Value> ops::IndexMut<String> for TSTMap<Value> {
+    #[inline]
+    fn index_mut(&mut self, idx: String) -> &mut Value {
+        &mut self[idx.as_str()]
+    }
+}
+
+impl<Value> ops::Index<&String> for TSTMap<Value> {
+    type Output = Value;
+    #[inline]
+    fn index(&self, idx: &String) -> &Value {
+        &self[idx.as_str()]
+    }
+}
+
+impl<Value> ops::IndexMut<&String> for TSTMap<Value> {
+    #[inline]
+    fn index_mut(&mut self, idx: &String) -> &mut Value {
+        &mut self[idx.as_str()]
+    }
+}
+
 impl<Value> Drop for TSTMap<Value> {
     fn drop(&mut self) {
         let root = self.root.take();
@@ -556,8 +3144,134 @@ impl<Value> Drop for TSTMap<Value> {
 
 impl<Value: Debug> Debug for TSTMap<Value> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_map().entries(self.iter()).finish()
+        let mut map = f.debug_map();
+        self.for_each_ref(|key, value| {
+            map.entry(&key, value);
+        });
+        map.finish()
+    }
+}
+
+/// Statistics about the shape of a `TSTMap`, returned by `TSTMap::stats`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TSTStats {
+    /// Number of nodes, including ones without values.
+    pub nodes: usize,
+    /// Number of nodes with values, which is the number of keys.
+    pub values: usize,
+    /// Number of nodes on the longest path from the root.
+    pub max_depth: usize,
+    /// Average length of keys in chars.
+    pub avg_key_len: f64,
+    /// Number of nodes with 0, 1, 2 and 3 children.
+    pub children: [usize; 4],
+}
+
+// key of the next entry of a map in merge_sorted, ordered by the order of chars of the maps
+struct MergeKey {
+    key: String,
+    idx: usize,
+    cmp: CharCmp,
+}
+
+impl PartialEq for MergeKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key && self.idx == other.idx
+    }
+}
+
+impl Eq for MergeKey {}
+
+impl PartialOrd for MergeKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MergeKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        traverse::compare_keys(&self.key, &other.key, self.cmp).then(self.idx.cmp(&other.idx))
+    }
+}
+
+/// Merges sorted entries of `maps` in a single pass into a new `TSTMap`. Values of a key
+/// present in several maps are combined with `resolve` in the order of the maps.
+/// The result is balanced like after `TSTMap::canonicalize` and takes the settings
+/// of the first map: normalization, order of chars, Bloom filter and `max_entries`.
+///
+/// All `maps` have to share the normalization and the order of chars of the first one.
+/// Otherwise their keys don't come in the same order and aren't stored the same way,
+/// so the same key may be overwritten by a later value instead of going to `resolve`.
+///
+/// # Examples
+///
+/// ```
+/// use tst::{merge_sorted, TSTMap};
+///
+/// let mut a = TSTMap::new();
+/// a.insert("a", 1);
+/// a.insert("b", 2);
+/// let mut b = TSTMap::new();
+/// b.insert("b", 10);
+/// b.insert("c", 20);
+///
+/// let m = merge_sorted(vec![a, b], |existing, incoming| *existing += incoming);
+/// assert_eq!(3, m.len());
+/// assert_eq!(12, m["b"]);
+/// ```
+pub fn merge_sorted<Value, I, F>(maps: I, mut resolve: F) -> TSTMap<Value>
+where
+    I: IntoIterator<Item = TSTMap<Value>>,
+    F: FnMut(&mut Value, Value),
+{
+    let maps: Vec<_> = maps.into_iter().collect();
+    let mut map = maps.first().map_or_else(TSTMap::new, TSTMap::empty_like);
+    let (form, cmp) = (map.form, map.char_cmp());
+    // keys are compared in the orientation they are stored and sorted in
+    let stored = |key: String| form.orient(key.into()).into_owned();
+    let mut iters: Vec<_> = maps.into_iter().map(TSTMap::into_iter).collect();
+    // the next value of every iterator, while its key waits in the heap
+    let mut heads: Vec<Option<Value>> = Vec::with_capacity(iters.len());
+    let mut heap = BinaryHeap::with_capacity(iters.len());
+    for (idx, iter) in iters.iter_mut().enumerate() {
+        heads.push(iter.next().map(|(key, value)| {
+            heap.push(Reverse(MergeKey {
+                key: stored(key),
+                idx,
+                cmp,
+            }));
+            value
+        }));
+    }
+
+    let mut last: Option<(String, Value)> = None;
+    while let Some(Reverse(MergeKey { key, idx, .. })) = heap.pop() {
+        let value = heads[idx].take().unwrap();
+        if let Some((next_key, next_value)) = iters[idx].next() {
+            heads[idx] = Some(next_value);
+            heap.push(Reverse(MergeKey {
+                key: stored(next_key),
+                idx,
+                cmp,
+            }));
+        }
+        last = match last {
+            Some((last_key, mut last_value)) if last_key == key => {
+                resolve(&mut last_value, value);
+                Some((last_key, last_value))
+            }
+            Some((last_key, last_value)) => {
+                map.insert(form.orient(last_key.into()), last_value);
+                Some((key, value))
+            }
+            None => Some((key, value)),
+        };
     }
+    if let Some((key, value)) = last {
+        map.insert(form.orient(key.into()), value);
+    }
+    map.canonicalize();
+    map
 }
 
 impl<Value> Default for TSTMap<Value> {
@@ -573,6 +3287,13 @@ impl<Value> Default for TSTMap<Value> {
             pool: Herd::new(),
             root: Default::default(),
             size: 0,
+            #[cfg(feature = "insertion-order")]
+            next_seq: 0,
+            form: KeyForm::default(),
+            bloom: None,
+            max_entries: None,
+            #[cfg(feature = "insertion-order")]
+            inserts: VecDeque::new(),
         }
     }
 }
@@ -582,31 +3303,133 @@ impl<Value> Default for TSTMap<Value> {
 //
 
 /// `TSTMap` iterator.
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct Iter<'x, Value: 'x> {
     iter: Traverse<'x, Value>,
+    // form of keys of the TSTMap to bring keys to skip to in and to turn keys back from
+    form: KeyForm,
+}
+
+impl<'x, Value> Default for Iter<'x, Value> {
+    fn default() -> Self {
+        Iter {
+            iter: Default::default(),
+            form: KeyForm::default(),
+        }
+    }
 }
 
 impl<'x, Value> Iter<'x, Value> {
-    fn new(node: NodeRef<'x, Value>, min: usize, max: usize) -> Self {
+    fn new(node: NodeRef<'x, Value>, form: KeyForm) -> Self {
+        Iter {
+            iter: Traverse::new(node),
+            form,
+        }
+    }
+    fn with_prefix(node: Option<&'x Node<Value>>, prefix: &str, form: KeyForm) -> Self {
         Iter {
-            iter: Traverse::new(node, min, max),
+            iter: Traverse::with_prefix(node, prefix),
+            form,
+        }
+    }
+    fn from_key(node: NodeRef<'x, Value>, start: &str, form: KeyForm) -> Self {
+        Iter {
+            iter: Traverse::from_key(node, start, form.cmp()),
+            form,
+        }
+    }
+
+    /// Skips all entries with keys smaller than `key`, so the next entry is the first one
+    /// not smaller than it. It takes time proportional to the length of `key` rather than
+    /// to the number of skipped entries. Entries yielded before are not yielded again,
+    /// if `key` is behind them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    /// let mut m = TSTMap::new();
+    /// m.insert("a", 1);
+    /// m.insert("b", 2);
+    /// m.insert("c", 3);
+    /// m.insert("d", 4);
+    ///
+    /// let mut iter = m.iter();
+    /// iter.advance_to("bb");
+    /// assert_eq!(Some(("c".to_string(), &3)), iter.next());
+    /// iter.advance_to("a");
+    /// assert_eq!(Some(("d".to_string(), &4)), iter.next());
+    /// assert_eq!(None, iter.next());
+    /// ```
+    pub fn advance_to(&mut self, key: &str) {
+        self.iter.advance_to(&self.form.apply(key), self.form.cmp())
+    }
+}
+
+impl<'x, Value> Iterator for Iter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        self.iter.next().map(|entry| self.form.restore(entry))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'x, Value> DoubleEndedIterator for Iter<'x, Value> {
+    fn next_back(&mut self) -> Option<(String, &'x Value)> {
+        self.iter.next_back().map(|entry| self.form.restore(entry))
+    }
+}
+
+impl<'x, Value> ExactSizeIterator for Iter<'x, Value> {}
+
+/// `TSTMap` iterator removing entries, which match a predicate.
+pub struct DrainFilter<'x, Value: 'x, F>
+where
+    F: FnMut(&str, &mut Value) -> bool,
+{
+    iter: Traverse<'x, Value>,
+    root: BoxedNodeRefMut<'x, Value>,
+    cont_size: &'x mut usize,
+    form: KeyForm,
+    pred: F,
+    // whether some nodes were left without values to detach them on drop
+    removed: bool,
+}
+
+impl<'x, Value, F> Iterator for DrainFilter<'x, Value, F>
+where
+    F: FnMut(&str, &mut Value) -> bool,
+{
+    type Item = (String, Value);
+    fn next(&mut self) -> Option<(String, Value)> {
+        while let Some((key, node)) = self.iter.next_node() {
+            // the traverse started from a mutable borrow of the TSTMap and yields every node once
+            let value = unsafe { node.value_mut() }?;
+            if (self.pred)(&self.form.orient(Cow::Borrowed(&key)), value) {
+                // keep nodes in place, as the traversal still points to them
+                let value = traverse::soft_remove(self.root.clone(), &key, self.form.cmp())?;
+                *self.cont_size -= 1;
+                self.removed = true;
+                return Some(self.form.restore((key, value)));
+            }
         }
+        None
     }
-    fn with_prefix(node: Option<&'x Node<Value>>, prefix: &str, max: usize) -> Self {
-        Iter {
-            iter: Traverse::with_prefix(node, prefix, max),
-        }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
     }
 }
 
-impl<'x, Value> Iterator for Iter<'x, Value> {
-    type Item = (String, &'x Value);
-    fn next(&mut self) -> Option<(String, &'x Value)> {
-        self.iter.next()
-    }
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        self.iter.size_hint()
+impl<'x, Value, F> Drop for DrainFilter<'x, Value, F>
+where
+    F: FnMut(&str, &mut Value) -> bool,
+{
+    fn drop(&mut self) {
+        if self.removed {
+            traverse::prune(self.root.clone());
+        }
     }
 }
 
@@ -614,17 +3437,24 @@ impl<'x, Value> Iterator for Iter<'x, Value> {
 #[derive(Clone, Default)]
 pub struct IterMut<'x, Value: 'x> {
     iter: Traverse<'x, Value>,
+    form: KeyForm,
+    // hands out mutable references to values
+    _marker: PhantomData<&'x mut Value>,
 }
 
 impl<'x, Value> IterMut<'x, Value> {
-    fn new(node: NodeRefMut<'x, Value>, min: usize, max: usize) -> Self {
+    fn new(node: NodeRefMut<'x, Value>, form: KeyForm) -> Self {
         IterMut {
-            iter: Traverse::new(node.into_immut(), min, max),
+            iter: Traverse::new(node.into_immut()),
+            form,
+            _marker: PhantomData,
         }
     }
-    fn with_prefix(ptr: Option<&'x Node<Value>>, prefix: &str, max: usize) -> Self {
+    fn with_prefix(ptr: Option<&'x Node<Value>>, prefix: &str, form: KeyForm) -> Self {
         IterMut {
-            iter: Traverse::with_prefix(ptr, prefix, max),
+            iter: Traverse::with_prefix(ptr, prefix),
+            form,
+            _marker: PhantomData,
         }
     }
 }
@@ -633,13 +3463,20 @@ impl<'x, Value> Iterator for IterMut<'x, Value> {
     type Item = (String, &'x mut Value);
     fn next(&mut self) -> Option<(String, &'x mut Value)> {
         // just add mut, avoid copy-paste
-        unsafe { mem::transmute(self.iter.next()) }
+        let entry = self.iter.next().map(|entry| self.form.restore(entry));
+        unsafe { mem::transmute(entry) }
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
         self.iter.size_hint()
     }
 }
 
+impl<'x, Value> ExactSizeIterator for IterMut<'x, Value> {}
+
+fn first<A, B>((k, _): (A, B)) -> A {
+    k
+}
+
 /// `TSTMap` keys iterator
 #[derive(Clone)]
 pub struct KeysIter<'x, Value: 'x> {
@@ -656,6 +3493,105 @@ impl<'x, Value: 'x> Iterator for KeysIter<'x, Value> {
     }
 }
 
+impl<'x, Value: 'x> DoubleEndedIterator for KeysIter<'x, Value> {
+    fn next_back(&mut self) -> Option<String> {
+        self.iter.next_back()
+    }
+}
+
+impl<'x, Value: 'x> ExactSizeIterator for KeysIter<'x, Value> {}
+
+/// `TSTMap` iterator with keys as filesystem paths.
+#[derive(Clone)]
+pub struct PathIter<'x, Value: 'x> {
+    iter: Iter<'x, Value>,
+}
+
+impl<'x, Value> Iterator for PathIter<'x, Value> {
+    type Item = (PathBuf, &'x Value);
+    fn next(&mut self) -> Option<(PathBuf, &'x Value)> {
+        self.iter
+            .next()
+            .map(|(key, value)| (PathBuf::from(key), value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'x, Value> ExactSizeIterator for PathIter<'x, Value> {}
+
+/// `TSTMap` iterator over groups of entries with the same first char of keys.
+pub struct FirstCharGroups<'x, Value: 'x> {
+    nodes: std::vec::IntoIter<&'x Node<Value>>,
+    form: KeyForm,
+}
+
+impl<'x, Value> Iterator for FirstCharGroups<'x, Value> {
+    type Item = (char, Iter<'x, Value>);
+    fn next(&mut self) -> Option<(char, Iter<'x, Value>)> {
+        loop {
+            let node = self.nodes.next()?;
+            // nodes left by removals may have no entries below
+            let iter = Iter::with_prefix(Some(node), &node.c.to_string(), self.form);
+            if iter.len() > 0 {
+                return Some((node.c, iter));
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.nodes.size_hint().1)
+    }
+}
+
+/// `TSTMap` iterator over keys, which are prefixes of a query, longest first.
+#[derive(Clone)]
+pub struct PrefixMatchesIter<'x, Value: 'x> {
+    query: &'x str,
+    iter: std::iter::Rev<std::vec::IntoIter<(Range<usize>, &'x Value)>>,
+}
+
+impl<'x, Value> Iterator for PrefixMatchesIter<'x, Value> {
+    type Item = (&'x str, &'x Value);
+    fn next(&mut self) -> Option<(&'x str, &'x Value)> {
+        self.iter
+            .next()
+            .map(|(range, value)| (&self.query[range], value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'x, Value> DoubleEndedIterator for PrefixMatchesIter<'x, Value> {
+    fn next_back(&mut self) -> Option<(&'x str, &'x Value)> {
+        self.iter
+            .next_back()
+            .map(|(range, value)| (&self.query[range], value))
+    }
+}
+
+impl<'x, Value> ExactSizeIterator for PrefixMatchesIter<'x, Value> {}
+
+/// `TSTMap` iterator over keys of a fixed length.
+#[derive(Clone)]
+pub struct KeysOfLengthIter<'x, Value: 'x> {
+    iter: WildCardTraverse<'x, Value>,
+    form: KeyForm,
+}
+
+impl<'x, Value: 'x> Iterator for KeysOfLengthIter<'x, Value> {
+    type Item = String;
+    fn next(&mut self) -> Option<String> {
+        self.iter
+            .next()
+            .map(|entry| first(self.form.restore(entry)))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
 /// `TSTMap` values iterator
 #[derive(Clone)]
 pub struct ValuesIter<'x, Value: 'x> {
@@ -672,16 +3608,79 @@ impl<'x, Value: 'x> Iterator for ValuesIter<'x, Value> {
     }
 }
 
+impl<'x, Value: 'x> DoubleEndedIterator for ValuesIter<'x, Value> {
+    fn next_back(&mut self) -> Option<&'x Value> {
+        self.iter.next_back()
+    }
+}
+
+impl<'x, Value: 'x> ExactSizeIterator for ValuesIter<'x, Value> {}
+
+/// `TSTMap` iterator in insertion order.
+#[cfg(feature = "insertion-order")]
+#[derive(Clone)]
+pub struct InsertionOrderIter<'x, Value: 'x> {
+    iter: std::vec::IntoIter<(String, &'x Value)>,
+    form: KeyForm,
+}
+
+#[cfg(feature = "insertion-order")]
+impl<'x, Value> Iterator for InsertionOrderIter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        self.iter.next().map(|entry| self.form.restore(entry))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// `TSTMap` typo-tolerant prefix iterator.
+#[derive(Clone)]
+pub struct FuzzyPrefixIter<'x, Value: 'x> {
+    iter: FuzzyPrefixTraverse<'x, Value>,
+    form: KeyForm,
+}
+
+impl<'x, Value> Iterator for FuzzyPrefixIter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        self.iter.next().map(|entry| self.form.restore(entry))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// `TSTMap` iterator over keys close to a query by Damerau-Levenshtein distance.
+#[derive(Clone)]
+pub struct DamerauIter<'x, Value: 'x> {
+    iter: DamerauTraverse<'x, Value>,
+    form: KeyForm,
+}
+
+impl<'x, Value> Iterator for DamerauIter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        self.iter.next().map(|entry| self.form.restore(entry))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
 /// `TSTMap` wild-card iterator.
 #[derive(Clone)]
 pub struct WildCardIter<'x, Value: 'x> {
     iter: WildCardTraverse<'x, Value>,
+    form: KeyForm,
 }
 
 impl<'x, Value> WildCardIter<'x, Value> {
-    fn new(node: NodeRef<'x, Value>, pat: &str, max: usize) -> Self {
+    fn new(node: NodeRef<'x, Value>, pat: &str, max: usize, form: KeyForm) -> Self {
         WildCardIter {
-            iter: WildCardTraverse::new(node, pat, max),
+            iter: WildCardTraverse::new(node, pat, form.reversed, max, form.cmp()),
+            form,
         }
     }
 }
@@ -689,7 +3688,7 @@ impl<'x, Value> WildCardIter<'x, Value> {
 impl<'x, Value> Iterator for WildCardIter<'x, Value> {
     type Item = (String, &'x Value);
     fn next(&mut self) -> Option<(String, &'x Value)> {
-        self.iter.next()
+        self.iter.next().map(|entry| self.form.restore(entry))
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
         self.iter.size_hint()
@@ -700,12 +3699,17 @@ impl<'x, Value> Iterator for WildCardIter<'x, Value> {
 #[derive(Clone)]
 pub struct WildCardIterMut<'x, Value: 'x> {
     iter: WildCardTraverse<'x, Value>,
+    form: KeyForm,
+    // hands out mutable references to values
+    _marker: PhantomData<&'x mut Value>,
 }
 
 impl<'x, Value> WildCardIterMut<'x, Value> {
-    fn new(node: NodeRefMut<'x, Value>, pat: &str, max: usize) -> Self {
+    fn new(node: NodeRefMut<'x, Value>, pat: &str, max: usize, form: KeyForm) -> Self {
         WildCardIterMut {
-            iter: WildCardTraverse::new(node.into_immut(), pat, max),
+            iter: WildCardTraverse::new(node.into_immut(), pat, form.reversed, max, form.cmp()),
+            form,
+            _marker: PhantomData,
         }
     }
 }
@@ -713,16 +3717,74 @@ impl<'x, Value> WildCardIterMut<'x, Value> {
 impl<'x, Value> Iterator for WildCardIterMut<'x, Value> {
     type Item = (String, &'x mut Value);
     fn next(&mut self) -> Option<(String, &'x mut Value)> {
-        unsafe { mem::transmute(self.iter.next()) }
+        let (key, node) = self.iter.next_node()?;
+        // the traverse started from a mutable borrow of the TSTMap for 'x
+        // and yields every node once
+        unsafe { node.value_mut() }.map(|value| self.form.restore((key, value)))
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
         self.iter.size_hint()
     }
 }
 
+/// `TSTMap` iterator over entries with keys ending with a match of a wildcard pattern.
+#[derive(Clone)]
+pub struct SuffixWildCardIter<'x, Value: 'x> {
+    iter: Iter<'x, Value>,
+    pat: SuffixPattern,
+}
+
+impl<'x, Value> Iterator for SuffixWildCardIter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        let pat = &self.pat;
+        self.iter.find(|(key, _)| pat.matches(key))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+/// `TSTMap` iterator over entries with keys matching a regular expression.
+#[cfg(feature = "regex")]
+pub struct RegexIter<'x, Value: 'x> {
+    iter: Iter<'x, Value>,
+    re: Regex,
+}
+
+#[cfg(feature = "regex")]
+impl<'x, Value> RegexIter<'x, Value> {
+    fn new(iter: Iter<'x, Value>, re: &Regex) -> Self {
+        // a pattern ending in a comment of the `x` mode needs a line break to close it,
+        // which would be matched literally otherwise
+        let re = ["", "\n"]
+            .iter()
+            .find_map(|end| Regex::new(&format!("^(?:{}{})$", re.as_str(), end)).ok())
+            .unwrap_or_else(|| re.clone());
+        RegexIter { iter, re }
+    }
+}
+
+#[cfg(feature = "regex")]
+impl<'x, Value> Iterator for RegexIter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn next(&mut self) -> Option<(String, &'x Value)> {
+        let re = &self.re;
+        // a pattern which could not be anchored is checked to match keys as a whole
+        self.iter.find(|(key, _)| {
+            re.find(key)
+                .is_some_and(|m| m.start() == 0 && m.end() == key.len())
+        })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
 /// `TSTMap` consuming iterator
 pub struct IntoIter<Value> {
     iter: IntoTraverse<Value>,
+    form: KeyForm,
 }
 
 impl<Value> IntoIter<Value> {
@@ -731,6 +3793,7 @@ impl<Value> IntoIter<Value> {
         let root = tst.root.take();
         IntoIter {
             iter: IntoTraverse::new(root, size),
+            form: tst.form,
         }
     }
 }
@@ -739,7 +3802,7 @@ impl<Value> Iterator for IntoIter<Value> {
     type Item = (String, Value);
 
     fn next(&mut self) -> Option<(String, Value)> {
-        self.iter.next()
+        self.iter.next().map(|entry| self.form.restore(entry))
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
         (self.iter.size, Some(self.iter.size))
@@ -752,6 +3815,154 @@ impl<Value> ExactSizeIterator for IntoIter<Value> {
     }
 }
 
+/// `TSTMap` parallel iterator.
+#[cfg(feature = "rayon")]
+pub struct ParIter<'x, Value: 'x> {
+    parts: Vec<Traverse<'x, Value>>,
+    form: KeyForm,
+}
+
+// parts only read nodes and hand out shared references to values,
+// so they could be walked by other threads when the values could be shared
+#[cfg(feature = "rayon")]
+unsafe impl<'x, Value: Sync> Send for ParIter<'x, Value> {}
+#[cfg(feature = "rayon")]
+unsafe impl<'x, Value: Sync> Sync for ParIter<'x, Value> {}
+
+// a part of the tree of a ParIter walked by one thread
+#[cfg(feature = "rayon")]
+struct ParPart<'x, Value: 'x>(Traverse<'x, Value>);
+
+#[cfg(feature = "rayon")]
+unsafe impl<'x, Value: Sync> Send for ParPart<'x, Value> {}
+
+#[cfg(feature = "rayon")]
+impl<'x, Value: Sync> ParallelIterator for ParIter<'x, Value> {
+    type Item = (String, &'x Value);
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let form = self.form;
+        let parts: Vec<_> = self.parts.into_iter().map(ParPart).collect();
+        parts
+            .into_par_iter()
+            .flat_map_iter(move |ParPart(iter)| Iter { iter, form })
+            .drive_unindexed(consumer)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'x, Value: Sync> IntoParallelIterator for &'x TSTMap<Value> {
+    type Item = (String, &'x Value);
+    type Iter = ParIter<'x, Value>;
+    fn into_par_iter(self) -> ParIter<'x, Value> {
+        self.par_iter()
+    }
+}
+
+//
+// Cursor section
+//
+
+/// A position at a prefix in a `TSTMap` to insert and look up keys by their suffixes.
+///
+/// With normalization of keys, the prefix and suffixes are normalized separately.
+pub struct Cursor<'x, Value: 'x> {
+    parts: Parts<'x, Value>,
+    // node of the last char of the prefix, None for the empty one
+    // and until a key is inserted under a prefix missing from the tree
+    node: Option<&'x mut Node<Value>>,
+    path: KeyPath<'x, Value>,
+    prefix: String,
+}
+
+impl<'x, Value> Cursor<'x, Value> {
+    /// Returns the prefix of the cursor.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Inserts the key made of the prefix and `suffix`, returning the previous value
+    /// of the key, like `TSTMap::insert` does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if both the prefix and `suffix` are empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// let mut cursor = m.cursor("ab");
+    /// assert_eq!(None, cursor.insert_suffix("c", 1));
+    /// assert_eq!(Some(1), cursor.insert_suffix("c", 2));
+    /// assert_eq!(None, cursor.insert_suffix("", 3));
+    ///
+    /// assert_eq!(Some(&2), m.get("abc"));
+    /// assert_eq!(Some(&3), m.get("ab"));
+    /// ```
+    pub fn insert_suffix<Key: AsRef<str>>(&mut self, suffix: Key, value: Value) -> Option<Value> {
+        let form = self.parts.form;
+        let suffix = form.apply(suffix.as_ref());
+        let suffix = suffix.as_ref();
+        let prefix = form.orient(Cow::Borrowed(&self.prefix));
+        let key = format!("{}{}", prefix, suffix);
+        let cmp = form.cmp();
+        let evicted = self.parts.prepare(&key).is_some();
+        if !prefix.is_empty() && (evicted || self.node.is_none()) {
+            // nodes of the prefix may be missing or detached with the evicted entry
+            let (path, node) =
+                traverse::insert(self.parts.root.clone(), &prefix, self.parts.pool, cmp);
+            self.path = path;
+            self.node = Some(node);
+        }
+        let (path, cur) = if suffix.is_empty() {
+            let node = self.node.as_deref_mut().expect("Empty key");
+            (KeyPath::default(), node)
+        } else {
+            let subtree = match self.node {
+                Some(ref mut node) => node.eq.as_mut(),
+                None => self.parts.root.clone(),
+            };
+            traverse::insert(subtree, suffix, self.parts.pool, cmp)
+        };
+        if cur.value.is_some() {
+            return cur.value.replace(value);
+        }
+        self.parts.put(path, cur, &key, value);
+        self.path.grow();
+        None
+    }
+
+    /// Returns a reference to the value of the key made of the prefix and `suffix`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("abc", 1);
+    /// let cursor = m.cursor("ab");
+    /// assert_eq!(Some(&1), cursor.get_suffix("c"));
+    /// assert_eq!(None, cursor.get_suffix(""));
+    /// ```
+    pub fn get_suffix<Key: AsRef<str>>(&self, suffix: Key) -> Option<&Value> {
+        let suffix = self.parts.form.apply(suffix.as_ref());
+        let subtree = match self.node {
+            Some(ref node) if suffix.is_empty() => return node.value.as_ref(),
+            Some(ref node) => node.eq.as_ref(),
+            None if !self.prefix.is_empty() => return None,
+            None => self.parts.root.as_mut().as_ref(),
+        };
+        let cmp = self.parts.form.cmp();
+        traverse::search(subtree, &suffix, cmp).and_then(|node| node.value.as_ref())
+    }
+}
+
 //
 // Entry section
 //
@@ -759,13 +3970,19 @@ impl<Value> ExactSizeIterator for IntoIter<Value> {
 /// A view into a single occupied location in a `TSTMap`.
 pub struct OccupiedEntry<'x, Value: 'x> {
     node: &'x mut Node<Value>,
-    cont_size: &'x mut usize,
+    path: KeyPath<'x, Value>,
+    // to remove the value or move it to another key
+    parts: Parts<'x, Value>,
+    // normalized key in its original orientation
+    key: Cow<'x, str>,
 }
 
 /// A view into a single empty location in a `TSTMap`.
+/// Nodes of the key are created only when a value is inserted.
 pub struct VacantEntry<'x, Value: 'x> {
-    node: &'x mut Node<Value>,
-    cont_size: &'x mut usize,
+    parts: Parts<'x, Value>,
+    // normalized key in its original orientation
+    key: Cow<'x, str>,
 }
 
 /// A view into a single location in a `TSTMap`, which may be vacant or occupied.
@@ -777,10 +3994,34 @@ pub enum Entry<'x, Value: 'x> {
 }
 
 impl<'x, Value> Entry<'x, Value> {
-    fn new(node: &'x mut Node<Value>, size: &'x mut usize) -> Self {
-        match node.value {
-            None => Vacant(VacantEntry::new(node, size)),
-            Some(_) => Occupied(OccupiedEntry::new(node, size)),
+    fn new(map: &'x mut TSTMap<Value>, key: Cow<'x, str>) -> Self {
+        let parts = map.parts();
+        let found = {
+            let stored = parts.form.orient(Cow::Borrowed(&key));
+            traverse::descend_mut(parts.root.clone(), &stored, parts.form.cmp())
+        };
+        match found {
+            Some((path, node)) if node.value.is_some() => Occupied(OccupiedEntry {
+                node,
+                path,
+                parts,
+                key,
+            }),
+            _ => Vacant(VacantEntry { parts, key }),
+        }
+    }
+    /// Gets a reference to the key of the entry.
+    pub fn key(&self) -> &str {
+        match *self {
+            Occupied(ref entry) => entry.key(),
+            Vacant(ref entry) => entry.key(),
+        }
+    }
+    /// Returns true if there are longer keys starting with the key of the entry.
+    pub fn has_descendants(&self) -> bool {
+        match *self {
+            Occupied(ref entry) => entry.has_descendants(),
+            Vacant(ref entry) => entry.has_descendants(),
         }
     }
     /// Gets a mut reference to the value in the entry or Err in case for Vacant.
@@ -806,14 +4047,47 @@ impl<'x, Value> Entry<'x, Value> {
             Vacant(entry) => entry.insert(default()),
         }
     }
+    /// Ensures a value is in the entry by inserting `default` if empty, applies `f` to
+    /// the value and returns a reference to the updated value.
+    pub fn modify_and_get<F: FnOnce(&mut Value)>(self, default: Value, f: F) -> &'x Value {
+        let value = self.or_insert(default);
+        f(value);
+        value
+    }
+    /// Ensures a value is in the entry by inserting the result of the default function
+    /// called with the entry's key if empty, and returns a mutable reference to the value
+    /// in the entry.
+    pub fn or_insert_with_key<F: FnOnce(&str) -> Value>(self, default: F) -> &'x mut Value {
+        match self {
+            Occupied(entry) => entry.into_mut(),
+            Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+    /// Ensures a value is in the entry by inserting the result of the fallible default
+    /// function if empty, and returns a mutable reference to the value in the entry.
+    /// If the function fails, the TSTMap is left unchanged and its error is returned.
+    pub fn or_try_insert_with<E, F: FnOnce() -> Result<Value, E>>(
+        self,
+        default: F,
+    ) -> Result<&'x mut Value, E> {
+        match self {
+            Occupied(entry) => Ok(entry.into_mut()),
+            Vacant(entry) => Ok(entry.insert(default()?)),
+        }
+    }
 }
 
 impl<'x, Value> OccupiedEntry<'x, Value> {
-    fn new(node: &'x mut Node<Value>, size: &'x mut usize) -> Self {
-        OccupiedEntry {
-            node,
-            cont_size: size,
-        }
+    /// Gets a reference to the key in the entry.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+    /// Returns true if there are longer keys starting with the key of the entry.
+    pub fn has_descendants(&self) -> bool {
+        self.node.eq.size() > 0
     }
     /// Gets a reference to the value in the entry.
     pub fn get(&self) -> &Value {
@@ -834,30 +4108,156 @@ impl<'x, Value> OccupiedEntry<'x, Value> {
     }
     /// Takes the value out of the entry, and returns it
     pub fn remove(self) -> Value {
-        *self.cont_size -= 1;
-        self.node.replace(None).unwrap()
+        self.path.shrink(1);
+        *self.parts.size -= 1;
+        self.node.value.take().unwrap()
+    }
+    /// Takes the key and value out of the entry, and returns them
+    pub fn remove_entry(self) -> (String, Value) {
+        self.path.shrink(1);
+        *self.parts.size -= 1;
+        (self.key.into_owned(), self.node.value.take().unwrap())
+    }
+    /// Moves the value of the entry to `new_key` in the same TSTMap, replacing the value
+    /// of `new_key`, which is returned, if there was one. Like `TSTMap::rename`, but
+    /// without looking up the entry again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_key` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tst::map::Entry;
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("colour", 1);
+    ///
+    /// if let Entry::Occupied(entry) = m.entry("colour") {
+    ///     assert_eq!(None, entry.move_to("color"));
+    /// }
+    /// assert_eq!(None, m.get("colour"));
+    /// assert_eq!(Some(&1), m.get("color"));
+    /// ```
+    pub fn move_to(mut self, new_key: &str) -> Option<Value> {
+        let new_key = self.parts.form.apply(new_key);
+        assert!(!new_key.is_empty(), "Empty key");
+        let value = self.node.value.take().unwrap();
+        self.path.shrink(1);
+        *self.parts.size -= 1;
+        let (path, node, _) = self.parts.locate(&new_key);
+        if node.value.is_some() {
+            return node.value.replace(value);
+        }
+        self.parts.put(path, node, &new_key, value);
+        None
     }
 }
 
 impl<'x, Value> VacantEntry<'x, Value> {
-    fn new(node: &'x mut Node<Value>, size: &'x mut usize) -> Self {
-        VacantEntry {
-            node,
-            cont_size: size,
-        }
+    /// Gets a reference to the key that would be used when inserting a value
+    /// through the VacantEntry.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+    /// Returns true if there are longer keys starting with the key of the entry.
+    pub fn has_descendants(&self) -> bool {
+        let root = self.parts.root.as_mut().as_ref();
+        let stored = self.parts.form.orient(Cow::Borrowed(&self.key));
+        traverse::search(root, &stored, self.parts.form.cmp())
+            .is_some_and(|node| node.eq.size() > 0)
+    }
+    /// Take ownership of the key.
+    pub fn into_key(self) -> String {
+        self.key.into_owned()
     }
     /// Sets the `value` of the entry with the VacantEntry's key,
     /// and returns a mutable reference to it
     pub fn insert(self, value: Value) -> &'x mut Value {
-        self.node.value = Some(value);
-        *self.cont_size += 1;
-        self.node.value.as_mut().unwrap()
+        let VacantEntry { mut parts, key } = self;
+        let key = parts.form.orient(key);
+        parts.get_or_insert_with(&key, || value)
     }
 }
 
 // internal tests
 #[cfg(test)]
 mod test {
+    #[test]
+    fn clone_from_reuses_nodes() {
+        let mut m = tstmap! {
+            "BY" => 1,
+            "BYE" => 2,
+            "XYZ" => 3,
+        };
+        let root = m.root.ptr;
+        let source = tstmap! {
+            "BY" => 10,
+            "BYGONE" => 30,
+        };
+        m.clone_from(&source);
+        assert_eq!(root, m.root.ptr);
+        assert_eq!(source, m);
+        assert_eq!(None, m.root.as_ref().gt.ptr);
+    }
+
+    #[test]
+    fn clear_prefix_drops_tails() {
+        let mut m = tstmap! {
+            "BY" => 1,
+            "BYGONE" => 3,
+            "BYE" => 2,
+        };
+        assert_eq!(3, m.clear_prefix("B"));
+        assert_eq!(None, m.root.ptr);
+    }
+
+    #[test]
+    fn entry_borrows_key() {
+        use super::{Cow, Entry};
+
+        let mut m = tstmap! {
+            "BY" => 1,
+        };
+        match m.entry("BY") {
+            Entry::Occupied(entry) => assert!(matches!(entry.key, Cow::Borrowed(_))),
+            Entry::Vacant(_) => panic!(),
+        }
+        match m.entry("BYE") {
+            Entry::Vacant(entry) => assert!(matches!(entry.key, Cow::Borrowed(_))),
+            Entry::Occupied(_) => panic!(),
+        }
+    }
+
+    #[test]
+    fn bloom_is_rebuilt_after_removals() {
+        let mut m = super::TSTMap::with_bloom(8);
+        for key in ["a", "b", "c", "d"] {
+            m.insert(key, 1);
+        }
+        m.remove("a");
+        m.remove("b");
+        assert!(m.bloom.as_ref().unwrap().contains("a"));
+        m.remove("c");
+        let bloom = m.bloom.as_ref().unwrap();
+        assert!(!bloom.contains("a") && !bloom.contains("c"));
+        assert!(bloom.contains("d"));
+
+        // removals through entries are caught up with on the next insert
+        for key in ["e", "f", "g"] {
+            m.insert(key, 1);
+            if let super::Entry::Occupied(entry) = m.entry(key) {
+                entry.remove();
+            }
+        }
+        m.insert("h", 1);
+        let bloom = m.bloom.as_ref().unwrap();
+        assert!(!bloom.contains("e") && !bloom.contains("g"));
+        assert!(bloom.contains("d") && bloom.contains("h"));
+    }
+
     #[test]
     fn remove_drops_tails() {
         let mut m = tstmap! {
@@ -1,5 +1,6 @@
 use core::marker::PhantomData;
 use core::ptr;
+use std::cmp::Ordering;
 use std::default::Default;
 use std::fmt::{self, Debug};
 use std::mem;
@@ -13,6 +14,11 @@ pub struct Node<Value> {
     pub gt: BoxedNode<Value>,
     pub value: Option<Value>,
     pub c: char,
+    // number of values in the subtree of the node, including its lt and gt children
+    pub size: usize,
+    // sequence number of the insert, which set the value
+    #[cfg(feature = "insertion-order")]
+    pub seq: usize,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -99,6 +105,11 @@ impl<Value> BoxedNode<Value> {
         self.ptr.is_some()
     }
 
+    // number of values in the subtree, 0 for an empty one
+    pub fn size(&self) -> usize {
+        self.as_ref().size()
+    }
+
     pub fn take(&mut self) -> Option<*mut Node<Value>> {
         self.ptr.take().map(|ptr| ptr as *mut Node<Value>)
     }
@@ -122,6 +133,24 @@ impl<'x, Value> NodeRef<'x, Value> {
             unsafe { Some(&*self.node) }
         }
     }
+
+    // number of values in the subtree, 0 for an empty one
+    pub fn size(&self) -> usize {
+        self.as_option().map_or(0, |node| node.size)
+    }
+}
+
+impl<'x, Value> NodeRef<'x, Value> {
+    /// Returns a mutable reference to the value of the node,
+    /// without making a shared reference to it first.
+    ///
+    /// # Safety
+    ///
+    /// The node has to be reached from a mutable borrow of its tree,
+    /// and there must be no other reference to its value for `'x`.
+    pub unsafe fn value_mut(self) -> Option<&'x mut Value> {
+        (*(self.node as *mut Node<Value>)).value.as_mut()
+    }
 }
 
 impl<'x, Value> Deref for NodeRef<'x, Value> {
@@ -141,6 +170,15 @@ impl<'x, Value> Default for NodeRef<'x, Value> {
     }
 }
 
+impl<'x, Value> From<&'x Node<Value>> for NodeRef<'x, Value> {
+    fn from(node: &'x Node<Value>) -> Self {
+        NodeRef {
+            node,
+            _marker: PhantomData,
+        }
+    }
+}
+
 impl<'x, Value> Clone for NodeRef<'x, Value> {
     fn clone(&self) -> Self {
         NodeRef {
@@ -215,13 +253,17 @@ impl<'x, Value> Clone for BoxedNodeRefMut<'x, Value> {
 }
 
 impl<Value> Node<Value> {
-    fn new(c: char) -> Node<Value> {
+    /// Constructs a node with char `c`, without a value and children.
+    pub fn new(c: char) -> Node<Value> {
         Node {
             lt: Default::default(),
             eq: Default::default(),
             gt: Default::default(),
             value: None,
             c,
+            size: 0,
+            #[cfg(feature = "insertion-order")]
+            seq: 0,
         }
     }
 
@@ -235,6 +277,41 @@ impl<Value> Node<Value> {
     pub fn replace(&mut self, value: Option<Value>) -> Option<Value> {
         mem::replace(&mut self.value, value)
     }
+
+    /// Returns the child of the node in direction `dir`: `Less` for the node with
+    /// a smaller char, `Equal` for the node with the next char of keys going through
+    /// this node and `Greater` for the node with a larger char.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use tst::TSTMap;
+    ///
+    /// let mut m = TSTMap::new();
+    /// m.insert("b", 1);
+    /// m.insert("bc", 2);
+    /// m.insert("a", 3);
+    ///
+    /// let root = m.root.as_ref().as_option().unwrap();
+    /// assert_eq!(Some(&1), root.value());
+    /// assert_eq!('a', root.child(Ordering::Less).unwrap().c);
+    /// assert_eq!(Some(&2), root.child(Ordering::Equal).unwrap().value());
+    /// assert!(root.child(Ordering::Greater).is_none());
+    /// ```
+    pub fn child(&self, dir: Ordering) -> Option<&Node<Value>> {
+        let child = match dir {
+            Ordering::Less => &self.lt,
+            Ordering::Equal => &self.eq,
+            Ordering::Greater => &self.gt,
+        };
+        child.as_ref().as_option()
+    }
+
+    /// Returns the value of the key ending at the node.
+    pub fn value(&self) -> Option<&Value> {
+        self.value.as_ref()
+    }
 }
 
 impl<Value: Debug> Debug for Node<Value> {
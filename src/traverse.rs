@@ -1,10 +1,36 @@
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::mem;
+use std::ops::ControlFlow;
 
 use bumpalo_herd::Herd;
 
 use super::node::{BoxedNode, BoxedNodeRefMut, Node, NodeRef, NodeRefMut};
 
+// order of chars in the tree, which decides between `lt`, `eq` and `gt` children
+pub type CharCmp = fn(char, char) -> Ordering;
+
+// the default order of chars, by their code points
+pub fn code_point_order(a: char, b: char) -> Ordering {
+    a.cmp(&b)
+}
+
+// compares keys char by char in the order `cmp`
+pub fn compare_keys(a: &str, b: &str, cmp: CharCmp) -> Ordering {
+    let (mut a, mut b) = (a.chars(), b.chars());
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match cmp(x, y) {
+                Ordering::Equal => {}
+                order => return order,
+            },
+        }
+    }
+}
+
 enum CompareResult<Handle> {
     GoLeftOrRight(Handle),
     GoDown(Handle),
@@ -30,6 +56,39 @@ impl<Ref> Trace<Ref> {
     }
 }
 
+// nodes passed on the way from the root to a key, each of them counts
+// the value of the key in its `size`
+pub struct KeyPath<'x, Value: 'x> {
+    stack: Trace<BoxedNodeRefMut<'x, Value>>,
+}
+
+impl<'x, Value> KeyPath<'x, Value> {
+    // to be called after the value of the key was set
+    pub fn grow(&self) {
+        for node in &self.stack.stack {
+            node.as_node_ref().size += 1;
+        }
+    }
+    // to be called after `count` values were taken from the subtree of the key
+    pub fn shrink(&self, count: usize) {
+        for node in &self.stack.stack {
+            node.as_node_ref().size -= count;
+        }
+    }
+    // number of nodes on the path
+    pub fn depth(&self) -> usize {
+        self.stack.stack.len()
+    }
+}
+
+impl<'x, Value> Default for KeyPath<'x, Value> {
+    fn default() -> Self {
+        KeyPath {
+            stack: Default::default(),
+        }
+    }
+}
+
 impl<Ref> Default for Trace<Ref> {
     fn default() -> Self {
         Trace { stack: vec![] }
@@ -42,55 +101,141 @@ enum TraverseEntry<NodeRef, ValueRef> {
     Value(ValueRef),
 }
 
+type TraverseStack<'x, Value> =
+    Trace<TraverseEntry<(String, NodeRef<'x, Value>), (String, NodeRef<'x, Value>)>>;
+
+type TraverseEntries<'x, Value> =
+    Vec<TraverseEntry<(String, NodeRef<'x, Value>), (String, NodeRef<'x, Value>)>>;
+
 #[derive(Clone)]
 pub struct Traverse<'x, Value: 'x> {
-    stack: Trace<TraverseEntry<(String, NodeRef<'x, Value>), (String, &'x Value)>>,
-    min_size: usize,
-    max_size: usize,
+    // the start of all keys, entries keep only parts of keys after it
+    prefix: String,
+    stack: TraverseStack<'x, Value>,
+    // entries to yield from the back, `stack` reversed at the start
+    back: TraverseStack<'x, Value>,
+    // number of values left to yield from both ends
+    size: usize,
 }
 
 impl<'x, Value> Traverse<'x, Value> {
-    pub fn new(node: NodeRef<'x, Value>, min: usize, max: usize) -> Self {
+    fn from_entries(
+        prefix: String,
+        entries: Vec<TraverseEntry<(String, NodeRef<'x, Value>), (String, NodeRef<'x, Value>)>>,
+        size: usize,
+    ) -> Self {
+        // the back stack pops the entries in the opposite order
         Traverse {
-            stack: Trace {
-                stack: vec![TraverseEntry::Node(("".to_string(), node))],
+            prefix,
+            back: Trace {
+                stack: entries.iter().rev().cloned().collect(),
             },
-            min_size: min,
-            max_size: max,
+            stack: Trace { stack: entries },
+            size,
         }
     }
 
-    pub fn with_prefix(node: Option<&'x Node<Value>>, prefix: &str, max: usize) -> Self {
-        let mut iter: Traverse<Value> = Default::default();
-        match node {
-            None => (),
-            Some(ptr) => {
-                iter.max_size = max;
-                if ptr.value.is_some() {
-                    iter.min_size += 1;
-                    iter.stack.push(TraverseEntry::Value((
-                        prefix.to_string(),
-                        ptr.value.as_ref().unwrap(),
-                    )));
+    pub fn new(node: NodeRef<'x, Value>) -> Self {
+        Self::subtree(node, String::new())
+    }
+
+    // all values of the subtree of `node` with `prefix` before their keys
+    pub fn subtree(node: NodeRef<'x, Value>, prefix: String) -> Self {
+        let size = node.size();
+        Self::from_entries(
+            prefix,
+            vec![TraverseEntry::Node((String::new(), node))],
+            size,
+        )
+    }
+
+    pub fn with_prefix(node: Option<&'x Node<Value>>, prefix: &str) -> Self {
+        let mut entries = vec![];
+        let mut size = 0;
+        if let Some(ptr) = node {
+            // entries are popped from the end, the prefix itself comes before longer keys
+            if ptr.eq.ptr.is_some() {
+                size += ptr.eq.size();
+                entries.push(TraverseEntry::Node((String::new(), ptr.eq.as_ref())));
+            }
+            if ptr.value.is_some() {
+                size += 1;
+                entries.push(TraverseEntry::Value((String::new(), NodeRef::from(ptr))));
+            }
+        }
+        Self::from_entries(prefix.to_string(), entries, size)
+    }
+
+    // all values with keys not smaller than `start`
+    pub fn from_key(node: NodeRef<'x, Value>, start: &str, cmp: CharCmp) -> Self {
+        let size = node.size() - rank(node.clone(), start, cmp);
+        Self::from_entries(
+            String::new(),
+            seek_entries(node, String::new(), start, cmp),
+            size,
+        )
+    }
+
+    // skips values with keys smaller than `start`, replacing the first entry, which
+    // keys may start with `start`, by the entries of its subtree not smaller than it
+    pub fn advance_to(&mut self, start: &str, cmp: CharCmp) {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value((suffix, node)) => {
+                    if compare_keys(&self.key(suffix.clone()), start, cmp) != Ordering::Less {
+                        self.stack.push(TraverseEntry::Value((suffix, node)));
+                        return;
+                    }
+                    self.size = self.size.saturating_sub(1);
                 }
-                if ptr.eq.ptr.is_some() {
-                    iter.stack
-                        .push(TraverseEntry::Node((prefix.to_string(), ptr.eq.as_ref())));
+                TraverseEntry::Node((prefix, node)) => {
+                    let key = self.key(prefix.clone());
+                    if let Some(rest) = start.strip_prefix(key.as_str()) {
+                        // values yielded from the back may be among the skipped ones
+                        self.size = self.size.saturating_sub(rank(node.clone(), rest, cmp));
+                        self.stack
+                            .stack
+                            .extend(seek_entries(node, prefix, rest, cmp));
+                        return;
+                    }
+                    if compare_keys(&key, start, cmp) == Ordering::Greater {
+                        self.stack.push(TraverseEntry::Node((prefix, node)));
+                        return;
+                    }
+                    self.size = self.size.saturating_sub(node.size());
                 }
             }
         }
-        iter
+    }
+
+    // the whole key of a value from its part after the prefix
+    fn key(&self, suffix: String) -> String {
+        if self.prefix.is_empty() {
+            return suffix;
+        }
+        let mut key = String::with_capacity(self.prefix.len() + suffix.len());
+        key.push_str(&self.prefix);
+        key.push_str(&suffix);
+        key
     }
 
     pub fn next(&mut self) -> Option<(String, &'x Value)> {
+        let (key, node) = self.next_node()?;
+        node.as_option()
+            .and_then(|cur| cur.value.as_ref())
+            .map(|value| (key, value))
+    }
+
+    // the same as `next`, but returns the node of the value
+    pub fn next_node(&mut self) -> Option<(String, NodeRef<'x, Value>)> {
+        if self.size == 0 {
+            return None;
+        }
         while let Some(entry) = self.stack.pop() {
             match entry {
-                TraverseEntry::Value((prefix, value)) => {
-                    if self.min_size == self.max_size {
-                        self.min_size -= 1;
-                    }
-                    self.max_size -= 1;
-                    return Some((prefix, value));
+                TraverseEntry::Value((suffix, node)) => {
+                    self.size -= 1;
+                    return Some((self.key(suffix), node));
                 }
                 TraverseEntry::Node((prefix, node)) => match node.as_option() {
                     None => {}
@@ -110,10 +255,8 @@ impl<'x, Value> Traverse<'x, Value> {
                             let mut new_prefix = String::with_capacity(prefix.len() + 1);
                             new_prefix.push_str(&prefix);
                             new_prefix.push(cur.c);
-                            self.stack.push(TraverseEntry::Value((
-                                new_prefix,
-                                cur.value.as_ref().unwrap(),
-                            )));
+                            self.stack
+                                .push(TraverseEntry::Value((new_prefix, node.clone())));
                         }
                         if cur.lt.is_some() {
                             self.stack
@@ -126,17 +269,161 @@ impl<'x, Value> Traverse<'x, Value> {
         None
     }
 
+    // the same as `next`, but children are visited in reverse order
+    pub fn next_back(&mut self) -> Option<(String, &'x Value)> {
+        if self.size == 0 {
+            return None;
+        }
+        while let Some(entry) = self.back.pop() {
+            match entry {
+                TraverseEntry::Value((suffix, node)) => {
+                    self.size -= 1;
+                    return node
+                        .as_option()
+                        .and_then(|cur| cur.value.as_ref())
+                        .map(|value| (self.key(suffix), value));
+                }
+                TraverseEntry::Node((prefix, node)) => {
+                    if let Some(cur) = node.as_option() {
+                        let mut new_prefix = String::with_capacity(prefix.len() + 1);
+                        new_prefix.push_str(&prefix);
+                        new_prefix.push(cur.c);
+                        if cur.lt.is_some() {
+                            self.back
+                                .push(TraverseEntry::Node((prefix.clone(), cur.lt.as_ref())));
+                        }
+                        if cur.value.is_some() {
+                            self.back
+                                .push(TraverseEntry::Value((new_prefix.clone(), node.clone())));
+                        }
+                        if cur.eq.is_some() {
+                            self.back
+                                .push(TraverseEntry::Node((new_prefix, cur.eq.as_ref())));
+                        }
+                        if cur.gt.is_some() {
+                            self.back
+                                .push(TraverseEntry::Node((prefix, cur.gt.as_ref())));
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
     pub fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.min_size, Some(self.max_size))
+        (self.size, Some(self.size))
+    }
+}
+
+// traverse entries of the subtree of `node` with keys not smaller than `start`,
+// which are the subtrees to the right of the path to `start`
+fn seek_entries<'x, Value>(
+    mut node: NodeRef<'x, Value>,
+    mut prefix: String,
+    start: &str,
+    cmp: CharCmp,
+) -> TraverseEntries<'x, Value> {
+    let mut entries = vec![];
+    let mut chars = start.chars().peekable();
+    if chars.peek().is_none() {
+        entries.push(TraverseEntry::Node((prefix.clone(), node.clone())));
+    }
+    while let (Some(&ch), Some(cur)) = (chars.peek(), node.as_option()) {
+        let order = cmp(ch, cur.c);
+        if order != Ordering::Greater && cur.gt.is_some() {
+            entries.push(TraverseEntry::Node((prefix.clone(), cur.gt.as_ref())));
+        }
+        match order {
+            Ordering::Less => {
+                let mut new_prefix = prefix.clone();
+                new_prefix.push(cur.c);
+                if cur.eq.is_some() {
+                    entries.push(TraverseEntry::Node((new_prefix.clone(), cur.eq.as_ref())));
+                }
+                if cur.value.is_some() {
+                    entries.push(TraverseEntry::Value((new_prefix, node.clone())));
+                }
+                node = cur.lt.as_ref();
+            }
+            Ordering::Greater => node = cur.gt.as_ref(),
+            Ordering::Equal => {
+                prefix.push(cur.c);
+                chars.next();
+                if chars.peek().is_none() {
+                    if cur.eq.is_some() {
+                        entries.push(TraverseEntry::Node((prefix.clone(), cur.eq.as_ref())));
+                    }
+                    if cur.value.is_some() {
+                        entries.push(TraverseEntry::Value((prefix.clone(), node.clone())));
+                    }
+                }
+                node = cur.eq.as_ref();
+            }
+        }
+    }
+    entries
+}
+
+#[cfg(feature = "rayon")]
+impl<'x, Value> Traverse<'x, Value> {
+    // splits the tree into at least `parts` traversals (if it is big enough),
+    // which together yield all values in sorted order
+    pub fn split(node: NodeRef<'x, Value>, parts: usize) -> Vec<Self> {
+        let mut entries = vec![TraverseEntry::Node(("".to_string(), node))];
+        loop {
+            let mut next = Vec::with_capacity(entries.len() * 4);
+            let mut split = false;
+            for entry in entries {
+                match entry {
+                    TraverseEntry::Node((prefix, node)) => {
+                        if let Some(cur) = node.as_option() {
+                            split = true;
+                            let mut new_prefix = String::with_capacity(prefix.len() + 1);
+                            new_prefix.push_str(&prefix);
+                            new_prefix.push(cur.c);
+                            if cur.lt.is_some() {
+                                next.push(TraverseEntry::Node((prefix.clone(), cur.lt.as_ref())));
+                            }
+                            if cur.value.is_some() {
+                                next.push(TraverseEntry::Value((new_prefix.clone(), node.clone())));
+                            }
+                            if cur.eq.is_some() {
+                                next.push(TraverseEntry::Node((new_prefix, cur.eq.as_ref())));
+                            }
+                            if cur.gt.is_some() {
+                                next.push(TraverseEntry::Node((prefix, cur.gt.as_ref())));
+                            }
+                        }
+                    }
+                    value => next.push(value),
+                }
+            }
+            entries = next;
+            if !split || entries.len() >= parts {
+                break;
+            }
+        }
+        entries
+            .into_iter()
+            .map(|entry| {
+                let size = match entry {
+                    TraverseEntry::Node((_, ref node)) => node.size(),
+                    TraverseEntry::Value(_) => 1,
+                };
+                Traverse::from_entries(String::new(), vec![entry], size)
+            })
+            .collect()
     }
 }
 
 impl<'x, Value> Default for Traverse<'x, Value> {
     fn default() -> Self {
         Traverse {
+            prefix: String::new(),
             stack: Default::default(),
-            min_size: 0,
-            max_size: 0,
+            back: Default::default(),
+            size: 0,
         }
     }
 }
@@ -246,29 +533,33 @@ impl<Value> DropTraverse<Value> {
 #[derive(Clone)]
 pub struct ValuesTraverse<'x, Value: 'x> {
     stack: Trace<TraverseEntry<NodeRef<'x, Value>, &'x Value>>,
-    min_size: usize,
-    max_size: usize,
+    // entries to yield from the back
+    back: Trace<TraverseEntry<NodeRef<'x, Value>, &'x Value>>,
+    // number of values left to yield from both ends
+    size: usize,
 }
 
 impl<'x, Value> ValuesTraverse<'x, Value> {
-    pub fn new(node: NodeRef<'x, Value>, min: usize, max: usize) -> Self {
+    pub fn new(node: NodeRef<'x, Value>) -> Self {
         ValuesTraverse {
             stack: Trace {
-                stack: vec![TraverseEntry::Node(node)],
+                stack: vec![TraverseEntry::Node(node.clone())],
             },
-            min_size: min,
-            max_size: max,
+            back: Trace {
+                stack: vec![TraverseEntry::Node(node.clone())],
+            },
+            size: node.size(),
         }
     }
 
     pub fn next(&mut self) -> Option<&'x Value> {
+        if self.size == 0 {
+            return None;
+        }
         while let Some(entry) = self.stack.pop() {
             match entry {
                 TraverseEntry::Value(value) => {
-                    if self.min_size == self.max_size {
-                        self.min_size -= 1;
-                    }
-                    self.max_size -= 1;
+                    self.size -= 1;
                     return Some(value);
                 }
                 TraverseEntry::Node(node) => match node.as_option() {
@@ -294,70 +585,282 @@ impl<'x, Value> ValuesTraverse<'x, Value> {
         None
     }
 
+    // the same as `next`, but children are visited in reverse order
+    pub fn next_back(&mut self) -> Option<&'x Value> {
+        if self.size == 0 {
+            return None;
+        }
+        while let Some(entry) = self.back.pop() {
+            match entry {
+                TraverseEntry::Value(value) => {
+                    self.size -= 1;
+                    return Some(value);
+                }
+                TraverseEntry::Node(node) => {
+                    if let Some(cur) = node.as_option() {
+                        if cur.lt.is_some() {
+                            self.back.push(TraverseEntry::Node(cur.lt.as_ref()));
+                        }
+                        if let Some(ref value) = cur.value {
+                            self.back.push(TraverseEntry::Value(value));
+                        }
+                        if cur.eq.is_some() {
+                            self.back.push(TraverseEntry::Node(cur.eq.as_ref()));
+                        }
+                        if cur.gt.is_some() {
+                            self.back.push(TraverseEntry::Node(cur.gt.as_ref()));
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
     pub fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.min_size, Some(self.max_size))
+        (self.size, Some(self.size))
+    }
+}
+
+// one position of a wildcard pattern
+#[derive(Clone)]
+enum WildCard {
+    Any,
+    // any sequence of chars, including the empty one
+    AnySeq,
+    Char(char),
+    // sorted chars of the class and negation flag
+    Class(Vec<char>, bool),
+}
+
+impl WildCard {
+    // `*` stands for any sequence of chars only in `glob` patterns,
+    // positions go from the last one with `rev`
+    fn parse(pat: &str, glob: bool, rev: bool) -> Vec<WildCard> {
+        let mut ret = vec![];
+        let mut chars = pat.chars();
+        while let Some(ch) = chars.next() {
+            let item = match ch {
+                '.' => WildCard::Any,
+                '*' if glob => WildCard::AnySeq,
+                '[' => {
+                    let rest = chars.as_str();
+                    match rest.find(']') {
+                        None => WildCard::Char(ch),
+                        Some(end) => {
+                            let (negated, class) = match rest[..end].strip_prefix('^') {
+                                Some(class) => (true, class),
+                                None => (false, &rest[..end]),
+                            };
+                            let mut class: Vec<char> = class.chars().collect();
+                            class.sort_unstable();
+                            chars = rest[end + 1..].chars();
+                            WildCard::Class(class, negated)
+                        }
+                    }
+                }
+                _ => WildCard::Char(ch),
+            };
+            ret.push(item);
+        }
+        if rev {
+            ret.reverse();
+        }
+        ret
+    }
+
+    fn go_left(&self, c: char, cmp: CharCmp) -> bool {
+        match *self {
+            WildCard::Any | WildCard::AnySeq | WildCard::Class(_, true) => true,
+            WildCard::Char(ch) => cmp(ch, c) == Ordering::Less,
+            WildCard::Class(ref class, false) => {
+                class.iter().any(|&ch| cmp(ch, c) == Ordering::Less)
+            }
+        }
+    }
+
+    fn go_down(&self, c: char) -> bool {
+        match *self {
+            WildCard::Any | WildCard::AnySeq => true,
+            WildCard::Char(ch) => ch == c,
+            WildCard::Class(ref class, negated) => class.binary_search(&c).is_ok() != negated,
+        }
+    }
+
+    fn go_right(&self, c: char, cmp: CharCmp) -> bool {
+        match *self {
+            WildCard::Any | WildCard::AnySeq | WildCard::Class(_, true) => true,
+            WildCard::Char(ch) => cmp(ch, c) == Ordering::Greater,
+            WildCard::Class(ref class, false) => {
+                class.iter().any(|&ch| cmp(ch, c) == Ordering::Greater)
+            }
+        }
+    }
+}
+
+// wildcard pattern matched against the ends of keys
+#[derive(Clone)]
+pub struct SuffixPattern {
+    pat: Vec<WildCard>,
+}
+
+impl SuffixPattern {
+    pub fn new(pat: &str) -> Self {
+        SuffixPattern {
+            pat: WildCard::parse(pat, false, false),
+        }
+    }
+
+    pub fn matches(&self, key: &str) -> bool {
+        let mut chars = key.chars().rev();
+        self.pat
+            .iter()
+            .rev()
+            .all(|item| chars.next().is_some_and(|c| item.go_down(c)))
     }
 }
 
 #[derive(Clone)]
 pub struct WildCardTraverse<'x, Value: 'x> {
-    stack: Trace<TraverseEntry<(String, NodeRef<'x, Value>, usize), (String, &'x Value)>>,
+    // values are kept as their nodes, so a mutable iterator could borrow them
+    stack: Trace<TraverseEntry<(String, NodeRef<'x, Value>, usize), (String, NodeRef<'x, Value>)>>,
     max_size: usize,
-    pat: Vec<char>,
+    pat: Vec<WildCard>,
+    cmp: CharCmp,
 }
 
 impl<'x, Value> WildCardTraverse<'x, Value> {
-    pub fn new(node: NodeRef<'x, Value>, pat: &str, max: usize) -> Self {
-        WildCardTraverse {
-            stack: Trace {
-                stack: vec![TraverseEntry::Node(("".to_string(), node, 0))],
-            },
-            max_size: max,
-            pat: pat.chars().collect(),
+    // with `rev` the pattern is matched against keys stored with reversed chars
+    pub fn new(node: NodeRef<'x, Value>, pat: &str, rev: bool, max: usize, cmp: CharCmp) -> Self {
+        Self::with_pattern(node, WildCard::parse(pat, false, rev), max, cmp)
+    }
+
+    // also `*` matches any sequence of chars, a key may be returned more than once
+    pub fn glob(node: NodeRef<'x, Value>, pat: &str, rev: bool, max: usize, cmp: CharCmp) -> Self {
+        Self::with_pattern(node, WildCard::parse(pat, true, rev), max, cmp)
+    }
+
+    // matches any key of exactly `len` chars, which needs no order of chars
+    pub fn with_length(node: NodeRef<'x, Value>, len: usize, max: usize) -> Self {
+        // the pattern is only built for a length some branch of the tree reaches
+        let len = if reaches_length(node.clone(), len) {
+            len
+        } else {
+            0
+        };
+        Self::with_pattern(node, vec![WildCard::Any; len], max, code_point_order)
+    }
+
+    fn with_pattern(
+        node: NodeRef<'x, Value>,
+        pat: Vec<WildCard>,
+        max: usize,
+        cmp: CharCmp,
+    ) -> Self {
+        let mut traverse = WildCardTraverse {
+            stack: Trace::new(1),
+            max_size: 0,
+            pat,
+            cmp,
+        };
+        if !traverse.pat.is_empty() {
+            let bound = Self::fixed_prefix_bound(node.clone(), &traverse.pat, cmp);
+            traverse.max_size = max.min(bound);
+            traverse.push_level(String::new(), node, 0);
         }
+        traverse
+    }
+
+    // pushes the level of nodes for the char at `idx` of the pattern, and for the next
+    // ones, if sequences at `idx` match no chars
+    fn push_level(&mut self, prefix: String, node: NodeRef<'x, Value>, idx: usize) {
+        let mut skip = idx;
+        while matches!(self.pat[skip], WildCard::AnySeq) && skip + 1 < self.pat.len() {
+            skip += 1;
+            self.stack
+                .push(TraverseEntry::Node((prefix.clone(), node.clone(), skip)));
+        }
+        self.stack.push(TraverseEntry::Node((prefix, node, idx)));
+    }
+
+    // number of values under the chars the pattern starts with, which bounds the matches
+    fn fixed_prefix_bound(node: NodeRef<'x, Value>, pat: &[WildCard], cmp: CharCmp) -> usize {
+        let fixed: String = pat
+            .iter()
+            .map_while(|item| match *item {
+                WildCard::Char(ch) => Some(ch),
+                _ => None,
+            })
+            .collect();
+        if fixed.is_empty() {
+            return node.size();
+        }
+        let len = fixed.chars().count();
+        match search(node, &fixed, cmp) {
+            None => 0,
+            Some(cur) => {
+                let fixed_matches = Self::matches_empty(&pat[len..]) && cur.value.is_some();
+                fixed_matches as usize + if len < pat.len() { cur.eq.size() } else { 0 }
+            }
+        }
+    }
+
+    // whether the rest of the pattern matches the empty string
+    fn matches_empty(pat: &[WildCard]) -> bool {
+        pat.iter().all(|item| matches!(item, WildCard::AnySeq))
     }
 
     pub fn next(&mut self) -> Option<(String, &'x Value)> {
+        let (key, node) = self.next_node()?;
+        node.as_option()
+            .and_then(|cur| cur.value.as_ref())
+            .map(|value| (key, value))
+    }
+
+    // returns the key and the node with the value of the next match
+    pub fn next_node(&mut self) -> Option<(String, NodeRef<'x, Value>)> {
         while let Some(entry) = self.stack.pop() {
             match entry {
-                TraverseEntry::Value((prefix, value)) => {
-                    self.max_size -= 1;
-                    return Some((prefix, value));
+                TraverseEntry::Value((prefix, node)) => {
+                    // glob patterns may match a key more than once
+                    self.max_size = self.max_size.saturating_sub(1);
+                    return Some((prefix, node));
                 }
                 TraverseEntry::Node((prefix, node, idx)) => match node.as_option() {
                     None => {}
                     Some(cur) => {
-                        let ch = self.pat[idx];
-                        if (ch == '.' || ch > cur.c) && cur.gt.is_some() {
+                        let ch = &self.pat[idx];
+                        let go_left = ch.go_left(cur.c, self.cmp);
+                        let go_down = ch.go_down(cur.c);
+                        // a sequence stays at the same position of the pattern after a char
+                        let next_idx = match ch {
+                            WildCard::AnySeq => idx,
+                            _ => idx + 1,
+                        };
+                        if ch.go_right(cur.c, self.cmp) && cur.gt.is_some() {
                             self.stack.push(TraverseEntry::Node((
                                 prefix.clone(),
                                 cur.gt.as_ref(),
                                 idx,
                             )));
                         }
-                        if ch == '.' || ch == cur.c {
-                            if idx + 1 < self.pat.len() && cur.eq.is_some() {
+                        if go_down {
+                            if next_idx < self.pat.len() && cur.eq.is_some() {
                                 let mut new_prefix = String::with_capacity(prefix.len() + 1);
                                 new_prefix.push_str(&prefix);
                                 new_prefix.push(cur.c);
-                                self.stack.push(TraverseEntry::Node((
-                                    new_prefix,
-                                    cur.eq.as_ref(),
-                                    idx + 1,
-                                )));
+                                self.push_level(new_prefix, cur.eq.as_ref(), next_idx);
                             }
 
-                            if idx + 1 == self.pat.len() && cur.value.is_some() {
+                            if Self::matches_empty(&self.pat[next_idx..]) && cur.value.is_some() {
                                 let mut new_prefix = String::with_capacity(prefix.len() + 1);
                                 new_prefix.push_str(&prefix);
                                 new_prefix.push(cur.c);
-                                self.stack.push(TraverseEntry::Value((
-                                    new_prefix,
-                                    cur.value.as_ref().unwrap(),
-                                )));
+                                self.stack
+                                    .push(TraverseEntry::Value((new_prefix, node.clone())));
                             }
                         }
-                        if (ch == '.' || ch < cur.c) && cur.lt.is_some() {
+                        if go_left && cur.lt.is_some() {
                             self.stack
                                 .push(TraverseEntry::Node((prefix, cur.lt.as_ref(), idx)));
                         }
@@ -372,13 +875,537 @@ impl<'x, Value> WildCardTraverse<'x, Value> {
     }
 }
 
+#[derive(Clone)]
+enum FuzzyPrefixEntry<'x, Value: 'x> {
+    // key before the node and the row of edit distances for it
+    Node((String, NodeRef<'x, Value>, Vec<usize>)),
+    // subtree below a key which is already close enough to the prefix
+    Matched(Traverse<'x, Value>),
+    Value((String, &'x Value)),
+}
+
+#[derive(Clone)]
+pub struct FuzzyPrefixTraverse<'x, Value: 'x> {
+    stack: Trace<FuzzyPrefixEntry<'x, Value>>,
+    max_size: usize,
+    query: Vec<char>,
+    max_dist: usize,
+}
+
+impl<'x, Value> FuzzyPrefixTraverse<'x, Value> {
+    pub fn new(node: NodeRef<'x, Value>, prefix: &str, max_dist: usize, max: usize) -> Self {
+        let query: Vec<char> = prefix.chars().collect();
+        let mut stack = Trace::new(1);
+        if query.len() <= max_dist {
+            // even the empty key is close enough, so every key matches
+            stack.push(FuzzyPrefixEntry::Matched(Traverse::new(node)));
+        } else {
+            stack.push(FuzzyPrefixEntry::Node((
+                "".to_string(),
+                node,
+                (0..=query.len()).collect(),
+            )));
+        }
+        FuzzyPrefixTraverse {
+            stack,
+            max_size: max,
+            query,
+            max_dist,
+        }
+    }
+
+    pub fn next(&mut self) -> Option<(String, &'x Value)> {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                FuzzyPrefixEntry::Matched(mut iter) => {
+                    if let Some(item) = iter.next() {
+                        self.stack.push(FuzzyPrefixEntry::Matched(iter));
+                        self.max_size -= 1;
+                        return Some(item);
+                    }
+                }
+                FuzzyPrefixEntry::Value(item) => {
+                    self.max_size -= 1;
+                    return Some(item);
+                }
+                FuzzyPrefixEntry::Node((prefix, node, row)) => {
+                    let cur = match node.as_option() {
+                        Some(cur) => cur,
+                        None => continue,
+                    };
+                    if cur.gt.is_some() {
+                        self.stack.push(FuzzyPrefixEntry::Node((
+                            prefix.clone(),
+                            cur.gt.as_ref(),
+                            row.clone(),
+                        )));
+                    }
+                    let next = edit_distance_row(&row, &self.query, cur.c);
+                    let mut new_prefix = String::with_capacity(prefix.len() + 1);
+                    new_prefix.push_str(&prefix);
+                    new_prefix.push(cur.c);
+                    if next[self.query.len()] <= self.max_dist {
+                        if cur.eq.is_some() {
+                            self.stack.push(FuzzyPrefixEntry::Matched(Traverse::subtree(
+                                cur.eq.as_ref(),
+                                new_prefix.clone(),
+                            )));
+                        }
+                        if let Some(ref value) = cur.value {
+                            self.stack
+                                .push(FuzzyPrefixEntry::Value((new_prefix, value)));
+                        }
+                    } else if cur.eq.is_some()
+                        && next.iter().min().is_some_and(|&d| d <= self.max_dist)
+                    {
+                        self.stack.push(FuzzyPrefixEntry::Node((
+                            new_prefix,
+                            cur.eq.as_ref(),
+                            next,
+                        )));
+                    }
+                    if cur.lt.is_some() {
+                        self.stack
+                            .push(FuzzyPrefixEntry::Node((prefix, cur.lt.as_ref(), row)));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.max_size))
+    }
+}
+
+// node with the rows of edit distances for the key before it and for that key
+// without its last char, which is kept for transpositions
+#[derive(Clone)]
+struct DamerauNode<'x, Value: 'x> {
+    prefix: String,
+    node: NodeRef<'x, Value>,
+    prev: Vec<usize>,
+    row: Vec<usize>,
+    last: Option<char>,
+}
+
+#[derive(Clone)]
+pub struct DamerauTraverse<'x, Value: 'x> {
+    stack: Trace<TraverseEntry<DamerauNode<'x, Value>, (String, &'x Value)>>,
+    max_size: usize,
+    query: Vec<char>,
+    max_dist: usize,
+}
+
+impl<'x, Value> DamerauTraverse<'x, Value> {
+    pub fn new(node: NodeRef<'x, Value>, query: &str, max_dist: usize, max: usize) -> Self {
+        let query: Vec<char> = query.chars().collect();
+        let mut stack = Trace::new(1);
+        stack.push(TraverseEntry::Node(DamerauNode {
+            prefix: String::new(),
+            node,
+            prev: vec![],
+            row: (0..=query.len()).collect(),
+            last: None,
+        }));
+        DamerauTraverse {
+            stack,
+            max_size: max,
+            query,
+            max_dist,
+        }
+    }
+
+    pub fn next(&mut self) -> Option<(String, &'x Value)> {
+        while let Some(entry) = self.stack.pop() {
+            match entry {
+                TraverseEntry::Value(item) => {
+                    self.max_size -= 1;
+                    return Some(item);
+                }
+                TraverseEntry::Node(state) => {
+                    let cur = match state.node.as_option() {
+                        Some(cur) => cur,
+                        None => continue,
+                    };
+                    if cur.gt.is_some() {
+                        self.stack.push(TraverseEntry::Node(DamerauNode {
+                            prefix: state.prefix.clone(),
+                            node: cur.gt.as_ref(),
+                            prev: state.prev.clone(),
+                            row: state.row.clone(),
+                            last: state.last,
+                        }));
+                    }
+                    let next = damerau_row(&state.prev, &state.row, &self.query, state.last, cur.c);
+                    let mut new_prefix = String::with_capacity(state.prefix.len() + 1);
+                    new_prefix.push_str(&state.prefix);
+                    new_prefix.push(cur.c);
+                    // a transposition with the next char may skip back to `row`
+                    let row_min = state.row.iter().min().map_or(0, |d| d + 1);
+                    if cur.eq.is_some()
+                        && next
+                            .iter()
+                            .min()
+                            .is_some_and(|&d| d.min(row_min) <= self.max_dist)
+                    {
+                        self.stack.push(TraverseEntry::Node(DamerauNode {
+                            prefix: new_prefix.clone(),
+                            node: cur.eq.as_ref(),
+                            prev: state.row.clone(),
+                            row: next.clone(),
+                            last: Some(cur.c),
+                        }));
+                    }
+                    if let Some(ref value) = cur.value {
+                        if next[self.query.len()] <= self.max_dist {
+                            self.stack.push(TraverseEntry::Value((new_prefix, value)));
+                        }
+                    }
+                    if cur.lt.is_some() {
+                        self.stack.push(TraverseEntry::Node(DamerauNode {
+                            node: cur.lt.as_ref(),
+                            ..state
+                        }));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.max_size))
+    }
+}
+
+pub fn visit<'x, Value, F>(node: NodeRef<'x, Value>, mut f: F)
+where
+    F: FnMut(&str, &'x Value, usize),
+{
+    visit_nodes(node, |key, node, depth| {
+        f(key, node.value.as_ref().unwrap(), depth)
+    })
+}
+
+// calls `f` for every node with its depth and the length of its key in chars
+pub fn walk_nodes<'x, Value, F>(node: NodeRef<'x, Value>, mut f: F)
+where
+    F: FnMut(&'x Node<Value>, usize, usize),
+{
+    let mut stack = Trace::new(0);
+    stack.push((node, 1, 1));
+    while let Some((node, depth, len)) = stack.pop() {
+        if let Some(cur) = node.as_option() {
+            f(cur, depth, len);
+            stack.push((cur.lt.as_ref(), depth + 1, len));
+            stack.push((cur.eq.as_ref(), depth + 1, len + 1));
+            stack.push((cur.gt.as_ref(), depth + 1, len));
+        }
+    }
+}
+
+// sorted distinct chars of all keys, nodes without values below them are skipped
+pub fn alphabet<Value>(node: NodeRef<Value>) -> Vec<char> {
+    let mut chars = BTreeSet::new();
+    // post-order walk, which keeps for every finished subtree if it has any values
+    let mut stack = Trace::new(0);
+    let mut has_values = vec![];
+    stack.push(TraverseEntry::Node(node));
+    while let Some(entry) = stack.pop() {
+        match entry {
+            TraverseEntry::Node(node) => match node.as_option() {
+                None => has_values.push(false),
+                Some(cur) => {
+                    stack.push(TraverseEntry::Value(cur));
+                    stack.push(TraverseEntry::Node(cur.lt.as_ref()));
+                    stack.push(TraverseEntry::Node(cur.eq.as_ref()));
+                    stack.push(TraverseEntry::Node(cur.gt.as_ref()));
+                }
+            },
+            TraverseEntry::Value(cur) => {
+                let lt = has_values.pop().unwrap();
+                let eq = has_values.pop().unwrap();
+                let gt = has_values.pop().unwrap();
+                let used = cur.value.is_some() || eq;
+                if used {
+                    chars.insert(cur.c);
+                }
+                has_values.push(used || lt || gt);
+            }
+        }
+    }
+    chars.into_iter().collect()
+}
+
+// nodes of the first level of the tree, linked by lt and gt, in the order of chars
+pub fn first_level<Value>(mut node: NodeRef<'_, Value>) -> Vec<&Node<Value>> {
+    let mut level = vec![];
+    let mut stack = Trace::new(0);
+    loop {
+        while let Some(cur) = node.as_option() {
+            stack.push(cur);
+            node = cur.lt.as_ref();
+        }
+        match stack.pop() {
+            None => return level,
+            Some(cur) => {
+                level.push(cur);
+                node = cur.gt.as_ref();
+            }
+        }
+    }
+}
+
+// checks the order of chars and sizes of all nodes, returns the number of values
+pub fn validate<Value>(node: NodeRef<Value>, cmp: CharCmp) -> Result<usize, String> {
+    let mut count = 0;
+    // every entry keeps the key before the node and the bounds of its char
+    let mut stack = Trace::new(0);
+    stack.push((node, String::new(), None, None));
+    while let Some((node, prefix, lo, hi)) = stack.pop() {
+        let cur = match node.as_option() {
+            None => continue,
+            Some(cur) => cur,
+        };
+        if lo.is_some_and(|lo| cmp(cur.c, lo) != Ordering::Greater)
+            || hi.is_some_and(|hi| cmp(cur.c, hi) != Ordering::Less)
+        {
+            return Err(format!(
+                "char {:?} after {:?} is out of bounds {:?}..{:?}",
+                cur.c, prefix, lo, hi
+            ));
+        }
+        let size = cur.value.is_some() as usize + cur.lt.size() + cur.eq.size() + cur.gt.size();
+        if cur.size != size {
+            return Err(format!(
+                "node {:?} after {:?} has size {}, but {} values below",
+                cur.c, prefix, cur.size, size
+            ));
+        }
+        if cur.value.is_some() {
+            count += 1;
+        }
+        let mut key = prefix.clone();
+        key.push(cur.c);
+        stack.push((cur.lt.as_ref(), prefix.clone(), lo, Some(cur.c)));
+        stack.push((cur.eq.as_ref(), key, None, None));
+        stack.push((cur.gt.as_ref(), prefix, Some(cur.c), hi));
+    }
+    Ok(count)
+}
+
+// the smallest key with its value, or the largest one with `rev`
+pub fn first_entry<Value>(node: NodeRef<'_, Value>, rev: bool) -> Option<(String, &Value)> {
+    // node entries keep the key length in bytes before it and an optional char to append
+    let mut stack = Trace::<
+        TraverseEntry<(NodeRef<Value>, usize, Option<char>), (&Node<Value>, usize)>,
+    >::new(0);
+    let mut key = String::new();
+    stack.push(TraverseEntry::Node((node, 0, None)));
+    while let Some(entry) = stack.pop() {
+        match entry {
+            TraverseEntry::Value((node, len)) => {
+                key.truncate(len);
+                key.push(node.c);
+                return Some((key, node.value.as_ref().unwrap()));
+            }
+            TraverseEntry::Node((node, len, ch)) => {
+                key.truncate(len);
+                if let Some(ch) = ch {
+                    key.push(ch);
+                }
+                if let Some(cur) = node.as_option() {
+                    let len = key.len();
+                    // entries are pushed in reverse of the visiting order
+                    let (first, last) = if rev {
+                        (&cur.gt, &cur.lt)
+                    } else {
+                        (&cur.lt, &cur.gt)
+                    };
+                    if last.is_some() {
+                        stack.push(TraverseEntry::Node((last.as_ref(), len, None)));
+                    }
+                    if rev && cur.value.is_some() {
+                        stack.push(TraverseEntry::Value((cur, len)));
+                    }
+                    if cur.eq.is_some() {
+                        stack.push(TraverseEntry::Node((cur.eq.as_ref(), len, Some(cur.c))));
+                    }
+                    if !rev && cur.value.is_some() {
+                        stack.push(TraverseEntry::Value((cur, len)));
+                    }
+                    if first.is_some() {
+                        stack.push(TraverseEntry::Node((first.as_ref(), len, None)));
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+// calls `f` for every node holding a value
+fn visit_nodes<'x, Value, F>(node: NodeRef<'x, Value>, mut f: F)
+where
+    F: FnMut(&str, &'x Node<Value>, usize),
+{
+    let _ = try_visit_nodes(node, |key, node, depth| {
+        f(key, node, depth);
+        ControlFlow::Continue(())
+    });
+}
+
+// calls `f` for every node holding a value in the order of keys, until it breaks
+pub fn try_visit_nodes<'x, Value, F>(node: NodeRef<'x, Value>, mut f: F) -> ControlFlow<()>
+where
+    F: FnMut(&str, &'x Node<Value>, usize) -> ControlFlow<()>,
+{
+    // every entry keeps the key length in bytes before it, an optional char to append
+    // and the depth
+    let mut stack = Trace::<
+        TraverseEntry<(NodeRef<Value>, usize, Option<char>, usize), (&Node<Value>, usize, usize)>,
+    >::new(0);
+    let mut key = String::new();
+    stack.push(TraverseEntry::Node((node, 0, None, 1)));
+    while let Some(entry) = stack.pop() {
+        match entry {
+            TraverseEntry::Value((node, len, depth)) => {
+                key.truncate(len);
+                key.push(node.c);
+                f(&key, node, depth)?;
+            }
+            TraverseEntry::Node((node, len, ch, depth)) => {
+                key.truncate(len);
+                if let Some(ch) = ch {
+                    key.push(ch);
+                }
+                if let Some(cur) = node.as_option() {
+                    let len = key.len();
+                    if cur.gt.is_some() {
+                        stack.push(TraverseEntry::Node((cur.gt.as_ref(), len, None, depth + 1)));
+                    }
+                    if cur.eq.is_some() {
+                        stack.push(TraverseEntry::Node((
+                            cur.eq.as_ref(),
+                            len,
+                            Some(cur.c),
+                            depth + 1,
+                        )));
+                    }
+                    if cur.value.is_some() {
+                        stack.push(TraverseEntry::Value((cur, len, depth)));
+                    }
+                    if cur.lt.is_some() {
+                        stack.push(TraverseEntry::Node((cur.lt.as_ref(), len, None, depth + 1)));
+                    }
+                }
+            }
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+// next row of Levenshtein distances between all prefixes of `query` and the key
+// extended with `c`, `row` is the one for the key itself
+fn edit_distance_row(row: &[usize], query: &[char], c: char) -> Vec<usize> {
+    let mut next = Vec::with_capacity(row.len());
+    next.push(row[0] + 1);
+    for (i, &qc) in query.iter().enumerate() {
+        let cost = if qc == c { 0 } else { 1 };
+        next.push((row[i] + cost).min(row[i + 1] + 1).min(next[i] + 1));
+    }
+    next
+}
+
+// the same as `edit_distance_row`, but a swap of `last` and `c` counts as one edit,
+// `prev` is the row before `last`
+fn damerau_row(
+    prev: &[usize],
+    row: &[usize],
+    query: &[char],
+    last: Option<char>,
+    c: char,
+) -> Vec<usize> {
+    let mut next = Vec::with_capacity(row.len());
+    next.push(row[0] + 1);
+    for (i, &qc) in query.iter().enumerate() {
+        let cost = if qc == c { 0 } else { 1 };
+        let mut dist = (row[i] + cost).min(row[i + 1] + 1).min(next[i] + 1);
+        if i > 0 && last == Some(qc) && query[i - 1] == c {
+            dist = dist.min(prev[i - 1] + 1);
+        }
+        next.push(dist);
+    }
+    next
+}
+
+#[cfg(feature = "insertion-order")]
+pub fn insertion_order<Value>(node: NodeRef<'_, Value>) -> Vec<(String, &Value)> {
+    let mut entries = vec![];
+    visit_nodes(node, |key, node, _| {
+        entries.push((node.seq, key.to_string(), node.value.as_ref().unwrap()))
+    });
+    entries.sort_unstable_by_key(|&(seq, _, _)| seq);
+    entries
+        .into_iter()
+        .map(|(_, key, value)| (key, value))
+        .collect()
+}
+
+// keys with sequence numbers of the inserts of their values, oldest first
+#[cfg(feature = "insertion-order")]
+pub fn keys_by_seq<Value>(node: NodeRef<'_, Value>) -> Vec<(usize, String)> {
+    let mut keys = vec![];
+    visit_nodes(node, |key, node, _| keys.push((node.seq, key.to_string())));
+    keys.sort_unstable();
+    keys
+}
+
+pub fn fuzzy<'x, Value, F>(node: NodeRef<'x, Value>, query: &str, max_dist: usize, mut f: F)
+where
+    F: FnMut(&str, &'x Value, usize),
+{
+    let query: Vec<char> = query.chars().collect();
+    // every entry keeps the key length in bytes before the node and the row of edit
+    // distances between that key and all prefixes of the query
+    let mut stack = Trace::<(NodeRef<Value>, usize, Vec<usize>)>::new(0);
+    let mut key = String::new();
+    stack.push((node, 0, (0..=query.len()).collect()));
+    while let Some((node, len, row)) = stack.pop() {
+        let cur = match node.as_option() {
+            Some(cur) => cur,
+            None => continue,
+        };
+        if cur.lt.is_some() {
+            stack.push((cur.lt.as_ref(), len, row.clone()));
+        }
+        if cur.gt.is_some() {
+            stack.push((cur.gt.as_ref(), len, row.clone()));
+        }
+        let next = edit_distance_row(&row, &query, cur.c);
+        key.truncate(len);
+        key.push(cur.c);
+        if let Some(ref value) = cur.value {
+            if next[query.len()] <= max_dist {
+                f(&key, value, next[query.len()]);
+            }
+        }
+        if cur.eq.is_some() && next.iter().min().is_some_and(|&d| d <= max_dist) {
+            stack.push((cur.eq.as_ref(), key.len(), next));
+        }
+    }
+}
+
 fn lookup_next<'x, Value>(
     node: &NodeRef<'x, Value>,
     ch: char,
+    cmp: CharCmp,
 ) -> CompareResult<NodeRef<'x, Value>> {
     match node.as_option() {
         None => CompareResult::NotFound,
-        Some(cur) => match ch.cmp(&cur.c) {
+        Some(cur) => match cmp(ch, cur.c) {
             Ordering::Less => CompareResult::GoLeftOrRight(cur.lt.as_ref()),
             Ordering::Greater => CompareResult::GoLeftOrRight(cur.gt.as_ref()),
             Ordering::Equal => CompareResult::GoDown(cur.eq.as_ref()),
@@ -389,12 +1416,13 @@ fn lookup_next<'x, Value>(
 fn lookup_next_mut<'x, Value>(
     node: &BoxedNodeRefMut<'x, Value>,
     ch: char,
+    cmp: CharCmp,
 ) -> CompareResult<BoxedNodeRefMut<'x, Value>> {
     match node.as_mut().ptr {
         None => CompareResult::NotFound,
         Some(ref cur) => {
             let cur = unsafe { &mut *(*cur as *mut Node<Value>) };
-            match ch.cmp(&cur.c) {
+            match cmp(ch, cur.c) {
                 Ordering::Less => CompareResult::GoLeftOrRight(cur.lt.as_mut()),
                 Ordering::Greater => CompareResult::GoLeftOrRight(cur.gt.as_mut()),
                 Ordering::Equal => CompareResult::GoDown(cur.eq.as_mut()),
@@ -403,13 +1431,17 @@ fn lookup_next_mut<'x, Value>(
     }
 }
 
-pub fn search<'x, Value>(mut node: NodeRef<'x, Value>, key: &str) -> Option<&'x Node<Value>> {
+pub fn search<'x, Value>(
+    mut node: NodeRef<'x, Value>,
+    key: &str,
+    cmp: CharCmp,
+) -> Option<&'x Node<Value>> {
     let mut last = Default::default();
 
     for ch in key.chars() {
         let mut go_next = false;
         while !go_next {
-            node = match lookup_next(&node, ch) {
+            node = match lookup_next(&node, ch, cmp) {
                 CompareResult::GoLeftOrRight(next) => next,
                 CompareResult::GoDown(next) => {
                     go_next = true;
@@ -425,21 +1457,31 @@ pub fn search<'x, Value>(mut node: NodeRef<'x, Value>, key: &str) -> Option<&'x
     last.as_option()
 }
 
+// finds or creates the node of `key`, `size`s on the returned path have to be
+// updated by the caller, if it sets the value
 pub fn insert<'x, Value>(
     mut node: BoxedNodeRefMut<'x, Value>,
     key: &str,
     pool: &mut Herd,
-) -> &'x mut Node<Value> {
-    let mut last = Default::default();
+    cmp: CharCmp,
+) -> (KeyPath<'x, Value>, &'x mut Node<Value>) {
+    let mut path = KeyPath {
+        stack: Trace::new(key.len()),
+    };
+    let mut last = BoxedNodeRefMut::default();
 
     for ch in key.chars() {
         let mut go_next = false;
         while !go_next {
-            node = match lookup_next_mut(&node, ch) {
-                CompareResult::GoLeftOrRight(next) => next,
+            node = match lookup_next_mut(&node, ch, cmp) {
+                CompareResult::GoLeftOrRight(next) => {
+                    path.stack.push(node);
+                    next
+                }
                 CompareResult::GoDown(next) => {
                     go_next = true;
-                    last = node;
+                    last = node.clone();
+                    path.stack.push(node);
                     next
                 }
                 CompareResult::NotFound => {
@@ -449,23 +1491,28 @@ pub fn insert<'x, Value>(
             }
         }
     }
-    last.as_node_ref()
+    (path, last.as_node_ref())
 }
 
 pub fn search_mut<'x, Value>(
     node: NodeRefMut<'x, Value>,
     key: &str,
+    cmp: CharCmp,
 ) -> Option<&'x mut Node<Value>> {
-    unsafe { mem::transmute(search(node.into_immut(), key)) }
+    unsafe { mem::transmute(search(node.into_immut(), key, cmp)) }
 }
 
-pub fn longest_prefix<'x, Value>(mut node: NodeRef<'x, Value>, pref: &'x str) -> &'x str {
+// length in chars of the longest key, which is a prefix of `query`
+pub fn longest_prefix_len<Value, I>(mut node: NodeRef<Value>, query: I, cmp: CharCmp) -> usize
+where
+    I: IntoIterator<Item = char>,
+{
     let mut length: usize = 0;
     let mut i: usize = 0;
-    for ch in pref.chars() {
+    for ch in query {
         let mut go_next = false;
         while !go_next {
-            node = match lookup_next(&node, ch) {
+            node = match lookup_next(&node, ch, cmp) {
                 CompareResult::GoLeftOrRight(next) => next,
                 CompareResult::GoDown(next) => {
                     go_next = true;
@@ -476,15 +1523,325 @@ pub fn longest_prefix<'x, Value>(mut node: NodeRef<'x, Value>, pref: &'x str) ->
                     next
                 }
                 CompareResult::NotFound => {
-                    return &pref[..length];
+                    return length;
                 }
             }
         }
     }
-    &pref[..length]
+    length
 }
 
-pub fn remove<Value>(mut node: BoxedNodeRefMut<Value>, key: &str) -> Option<Value> {
+// values of all keys, which are prefixes of `query`, with their lengths in bytes
+pub fn prefix_values<'x, Value>(
+    mut node: NodeRef<'x, Value>,
+    query: &str,
+    cmp: CharCmp,
+) -> Vec<(usize, &'x Value)> {
+    let mut found = vec![];
+    for (i, ch) in query.char_indices() {
+        loop {
+            let cur = match node.as_option() {
+                Some(cur) => cur,
+                None => return found,
+            };
+            match cmp(ch, cur.c) {
+                Ordering::Less => node = cur.lt.as_ref(),
+                Ordering::Greater => node = cur.gt.as_ref(),
+                Ordering::Equal => {
+                    if let Some(ref value) = cur.value {
+                        found.push((i + ch.len_utf8(), value));
+                    }
+                    node = cur.eq.as_ref();
+                    break;
+                }
+            }
+        }
+    }
+    found
+}
+
+// moves all nodes of the tree `from` into `node` allocated from `pool` in pre-order,
+// where the `eq` child goes right after its parent, as lookups mostly follow it
+pub fn relayout<Value>(
+    node: BoxedNodeRefMut<Value>,
+    from: BoxedNodeRefMut<Value>,
+    pool: &mut Herd,
+) {
+    let mut stack = Trace::new(0);
+    stack.push((node, from));
+    while let Some((mut node, from)) = stack.pop() {
+        if !from.as_mut().is_some() {
+            continue;
+        }
+        let from = from.as_node_ref();
+        node.assign(BoxedNode::new(from.c, pool));
+        let cur = node.as_node_ref();
+        cur.value = from.value.take();
+        cur.size = from.size;
+        #[cfg(feature = "insertion-order")]
+        {
+            cur.seq = from.seq;
+        }
+        stack.push((cur.gt.as_mut(), from.gt.as_mut()));
+        stack.push((cur.lt.as_mut(), from.lt.as_mut()));
+        stack.push((cur.eq.as_mut(), from.eq.as_mut()));
+    }
+}
+
+// moves all values of the tree `from` into `node` allocated from `pool` inserting keys
+// in the order of medians, so the shape of the tree depends only on the set of keys
+pub fn rebalance<Value>(
+    node: BoxedNodeRefMut<Value>,
+    from: BoxedNodeRefMut<Value>,
+    pool: &mut Herd,
+    cmp: CharCmp,
+) {
+    let mut keys = vec![];
+    visit_nodes(from.as_mut().as_ref(), |key, _, _| {
+        keys.push(key.to_string())
+    });
+
+    let mut ranges = vec![(0, keys.len())];
+    while let Some((lo, hi)) = ranges.pop() {
+        if lo >= hi {
+            continue;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let (_, src) = descend_mut(from.clone(), &keys[mid], cmp).unwrap();
+        let (path, cur) = insert(node.clone(), &keys[mid], pool, cmp);
+        cur.value = src.value.take();
+        #[cfg(feature = "insertion-order")]
+        {
+            cur.seq = src.seq;
+        }
+        path.grow();
+        ranges.push((mid + 1, hi));
+        ranges.push((lo, mid));
+    }
+}
+
+// moves all values of keys starting with `c` from the tree `from` into `node` allocated
+// from `pool`, keeping the shape of their subtree and skipping parts without values
+pub fn move_branch<Value>(
+    mut node: BoxedNodeRefMut<Value>,
+    from: BoxedNodeRefMut<Value>,
+    c: char,
+    pool: &mut Herd,
+    cmp: CharCmp,
+) {
+    let src = match descend_mut(from, c.encode_utf8(&mut [0; 4]), cmp) {
+        Some((_, src)) => src,
+        None => return,
+    };
+    // lt and gt of the node belong to other chars
+    let size = src.value.is_some() as usize + src.eq.size();
+    if size == 0 {
+        return;
+    }
+    node.assign(BoxedNode::new(c, pool));
+    let cur = node.as_node_ref();
+    cur.value = src.value.take();
+    #[cfg(feature = "insertion-order")]
+    {
+        cur.seq = src.seq;
+    }
+    cur.size = size;
+
+    let mut stack = Trace::new(0);
+    stack.push((cur.eq.as_mut(), src.eq.as_mut()));
+    while let Some((mut node, from)) = stack.pop() {
+        if from.as_mut().size() == 0 {
+            continue;
+        }
+        let src = from.as_node_ref();
+        node.assign(BoxedNode::new(src.c, pool));
+        let cur = node.as_node_ref();
+        cur.value = src.value.take();
+        #[cfg(feature = "insertion-order")]
+        {
+            cur.seq = src.seq;
+        }
+        cur.size = src.size;
+        stack.push((cur.lt.as_mut(), src.lt.as_mut()));
+        stack.push((cur.eq.as_mut(), src.eq.as_mut()));
+        stack.push((cur.gt.as_mut(), src.gt.as_mut()));
+    }
+}
+
+pub fn clone_from<Value: Clone>(
+    node: BoxedNodeRefMut<Value>,
+    from: NodeRef<Value>,
+    pool: &mut Herd,
+) {
+    let mut stack = Trace::new(0);
+    stack.push((node, from));
+    while let Some((mut node, from)) = stack.pop() {
+        match from.as_option() {
+            None => {
+                let mut iter = DropTraverse::new(node.as_mut().take());
+                while iter.next().is_some() {}
+            }
+            Some(from) => {
+                if !node.as_mut().is_some() {
+                    node.assign(BoxedNode::new(from.c, pool));
+                }
+                let cur = node.as_node_ref();
+                cur.c = from.c;
+                cur.value.clone_from(&from.value);
+                cur.size = from.size;
+                #[cfg(feature = "insertion-order")]
+                {
+                    cur.seq = from.seq;
+                }
+                stack.push((cur.lt.as_mut(), from.lt.as_ref()));
+                stack.push((cur.eq.as_mut(), from.eq.as_ref()));
+                stack.push((cur.gt.as_mut(), from.gt.as_ref()));
+            }
+        }
+    }
+}
+
+// length in chars of the longest common prefix of `a` and `b`, which is a path
+// in the tree
+pub fn divergence_point<Value>(mut node: NodeRef<Value>, a: &str, b: &str, cmp: CharCmp) -> usize {
+    let mut length: usize = 0;
+
+    for (ch, other) in a.chars().zip(b.chars()) {
+        if ch != other {
+            break;
+        }
+        let mut go_next = false;
+        while !go_next {
+            node = match lookup_next(&node, ch, cmp) {
+                CompareResult::GoLeftOrRight(next) => next,
+                // nodes left without values by removals are not a prefix of any key
+                CompareResult::GoDown(next)
+                    if next.size() > 0
+                        || node.as_option().is_some_and(|cur| cur.value.is_some()) =>
+                {
+                    go_next = true;
+                    length += 1;
+                    next
+                }
+                CompareResult::GoDown(_) | CompareResult::NotFound => {
+                    return length;
+                }
+            }
+        }
+    }
+    length
+}
+
+// number of values with keys starting with `prefix`
+pub fn count_prefix<Value>(node: NodeRef<Value>, prefix: &str, cmp: CharCmp) -> usize {
+    match search(node, prefix, cmp) {
+        None => 0,
+        Some(cur) => cur.value.is_some() as usize + cur.eq.size(),
+    }
+}
+
+// entry with exactly `n` smaller keys
+pub fn nth<Value>(mut node: NodeRef<'_, Value>, mut n: usize) -> Option<(String, &Value)> {
+    let mut key = String::new();
+    while let Some(cur) = node.as_option() {
+        let lt = cur.lt.size();
+        if n < lt {
+            node = cur.lt.as_ref();
+            continue;
+        }
+        n -= lt;
+        if let Some(ref value) = cur.value {
+            if n == 0 {
+                key.push(cur.c);
+                return Some((key, value));
+            }
+            n -= 1;
+        }
+        let eq = cur.eq.size();
+        if n < eq {
+            key.push(cur.c);
+            node = cur.eq.as_ref();
+        } else {
+            n -= eq;
+            node = cur.gt.as_ref();
+        }
+    }
+    None
+}
+
+// number of keys smaller than `key`
+pub fn rank<Value>(mut node: NodeRef<Value>, key: &str, cmp: CharCmp) -> usize {
+    let mut rank = 0;
+    let mut chars = key.chars().peekable();
+    while let (Some(&ch), Some(cur)) = (chars.peek(), node.as_option()) {
+        match cmp(ch, cur.c) {
+            Ordering::Less => node = cur.lt.as_ref(),
+            Ordering::Greater => {
+                rank += cur.size - cur.gt.size();
+                node = cur.gt.as_ref();
+            }
+            Ordering::Equal => {
+                rank += cur.lt.size();
+                chars.next();
+                if chars.peek().is_some() && cur.value.is_some() {
+                    rank += 1;
+                }
+                node = cur.eq.as_ref();
+            }
+        }
+    }
+    rank
+}
+
+// whether some branch of the tree is at least `len` chars deep,
+// looking at no nodes deeper than that
+fn reaches_length<Value>(node: NodeRef<Value>, len: usize) -> bool {
+    let mut stack = vec![(node, 1)];
+    while let Some((node, depth)) = stack.pop() {
+        if let Some(cur) = node.as_option() {
+            if depth >= len {
+                return true;
+            }
+            stack.push((cur.lt.as_ref(), depth));
+            stack.push((cur.gt.as_ref(), depth));
+            stack.push((cur.eq.as_ref(), depth + 1));
+        }
+    }
+    false
+}
+
+pub fn common_prefix<Value>(mut node: NodeRef<Value>) -> String {
+    let mut prefix = String::new();
+    while let Some(cur) = node.as_option() {
+        // removals may leave nodes without values, which don't split the keys
+        let (lt, gt) = (cur.lt.size(), cur.gt.size());
+        if cur.size == lt + gt {
+            // no key goes through this char, follow the only side with keys
+            node = match (lt > 0, gt > 0) {
+                (true, false) => cur.lt.as_ref(),
+                (false, true) => cur.gt.as_ref(),
+                _ => break,
+            };
+            continue;
+        }
+        if lt > 0 || gt > 0 {
+            break;
+        }
+        prefix.push(cur.c);
+        if cur.value.is_some() {
+            break;
+        }
+        node = cur.eq.as_ref();
+    }
+    prefix
+}
+
+// finds the node of `key` with the path to it, without creating nodes
+pub fn descend_mut<'x, Value>(
+    mut node: BoxedNodeRefMut<'x, Value>,
+    key: &str,
+    cmp: CharCmp,
+) -> Option<(KeyPath<'x, Value>, &'x mut Node<Value>)> {
     let mut stack = Trace::<BoxedNodeRefMut<Value>>::new(key.len());
     let mut ptr = None;
 
@@ -492,7 +1849,7 @@ pub fn remove<Value>(mut node: BoxedNodeRefMut<Value>, key: &str) -> Option<Valu
         let mut go_next = false;
         while !go_next {
             stack.push(node.clone());
-            node = match lookup_next_mut(&node, ch) {
+            node = match lookup_next_mut(&node, ch, cmp) {
                 CompareResult::GoLeftOrRight(next) => next,
                 CompareResult::GoDown(next) => {
                     go_next = true;
@@ -505,19 +1862,110 @@ pub fn remove<Value>(mut node: BoxedNodeRefMut<Value>, key: &str) -> Option<Valu
             }
         }
     }
-    let ret = match ptr {
-        None => None,
-        Some(ptr) => ptr.value.take(),
-    };
-    // cut the tail
-    if ret.is_some() {
-        while let Some(mut node_to_drop) = stack.pop() {
-            let ptr = node_to_drop.as_node_ref();
-            if !ptr.is_leaf() {
-                break;
-            }
-            node_to_drop.assign(Default::default());
+    ptr.map(|ptr| (KeyPath { stack }, ptr))
+}
+
+// detaches the nodes left without values at the end of `path`, returns their number
+fn cut_tail<Value>(mut path: KeyPath<Value>) -> usize {
+    let mut count = 0;
+    while let Some(mut node_to_drop) = path.stack.pop() {
+        let ptr = node_to_drop.as_node_ref();
+        if !ptr.is_leaf() {
+            break;
         }
+        node_to_drop.assign(Default::default());
+        count += 1;
+    }
+    count
+}
+
+// returns the value of `key` and the number of detached nodes
+pub fn remove<Value>(
+    node: BoxedNodeRefMut<Value>,
+    key: &str,
+    cmp: CharCmp,
+) -> Option<(Value, usize)> {
+    remove_if(node, key, cmp, |_| true)
+}
+
+// removes the value of `key` only if `pred` accepts it
+pub fn remove_if<Value, F>(
+    node: BoxedNodeRefMut<Value>,
+    key: &str,
+    cmp: CharCmp,
+    pred: F,
+) -> Option<(Value, usize)>
+where
+    F: FnOnce(&Value) -> bool,
+{
+    let (path, ptr) = descend_mut(node, key, cmp)?;
+    if !pred(ptr.value.as_ref()?) {
+        return None;
+    }
+    let ret = ptr.value.take()?;
+    path.shrink(1);
+    Some((ret, cut_tail(path)))
+}
+
+// takes the value of `key`, but keeps its nodes for a later insert of it
+pub fn soft_remove<Value>(node: BoxedNodeRefMut<Value>, key: &str, cmp: CharCmp) -> Option<Value> {
+    let (path, ptr) = descend_mut(node, key, cmp)?;
+    let ret = ptr.value.take();
+    if ret.is_some() {
+        path.shrink(1);
     }
     ret
 }
+
+// detaches all subtrees without values
+pub fn prune<Value>(node: BoxedNodeRefMut<Value>) {
+    let mut stack = Trace::new(0);
+    stack.push(node);
+    while let Some(mut node) = stack.pop() {
+        if !node.as_mut().is_some() {
+            continue;
+        }
+        if node.as_mut().size() == 0 {
+            node.assign(Default::default());
+            continue;
+        }
+        let cur = node.as_node_ref();
+        stack.push(cur.lt.as_mut());
+        stack.push(cur.eq.as_mut());
+        stack.push(cur.gt.as_mut());
+    }
+}
+
+pub fn clear_prefix<Value>(node: BoxedNodeRefMut<Value>, prefix: &str, cmp: CharCmp) -> usize {
+    clear_below(node, prefix, false, cmp)
+}
+
+// removes keys longer than `prefix` starting with it, `prefix` itself stays
+pub fn clear_children<Value>(node: BoxedNodeRefMut<Value>, prefix: &str, cmp: CharCmp) -> usize {
+    clear_below(node, prefix, true, cmp)
+}
+
+fn clear_below<Value>(
+    node: BoxedNodeRefMut<Value>,
+    prefix: &str,
+    keep_prefix: bool,
+    cmp: CharCmp,
+) -> usize {
+    let (path, ptr) = match descend_mut(node, prefix, cmp) {
+        None => return 0,
+        Some(found) => found,
+    };
+    let mut count = 0;
+    if !keep_prefix && ptr.value.take().is_some() {
+        count += 1;
+    }
+    let mut iter = DropTraverse::new(ptr.eq.take());
+    while iter.next().is_some() {
+        count += 1;
+    }
+    if count > 0 {
+        path.shrink(count);
+        cut_tail(path);
+    }
+    count
+}
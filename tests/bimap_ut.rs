@@ -0,0 +1,82 @@
+extern crate tst;
+
+use self::tst::TSTBiMap;
+
+fn assert_consistent(m: &TSTBiMap<u32>) {
+    for (key, value) in m.iter() {
+        assert_eq!(Some(key.as_str()), m.get_by_value(value));
+        assert_eq!(Some(value), m.get_by_key(&key));
+    }
+}
+
+#[test]
+fn insert() {
+    let mut m = TSTBiMap::new();
+
+    assert_eq!((None, None), m.insert("alice", 1));
+    assert_eq!((None, None), m.insert("bob", 2));
+    assert_eq!((None, None), m.insert("carol", 3));
+    assert_eq!((Some(1), None), m.insert("alice", 4));
+    assert_eq!(3, m.len());
+    assert_eq!(None, m.get_by_value(&1));
+    assert_eq!(Some("alice"), m.get_by_value(&4));
+    assert_consistent(&m);
+
+    // both the key and the value were paired
+    assert_eq!((Some(2), Some("carol".to_string())), m.insert("bob", 3));
+    assert_eq!(2, m.len());
+    assert_eq!(None, m.get_by_key("carol"));
+    assert_eq!(None, m.get_by_value(&2));
+    assert_consistent(&m);
+
+    // the same pair again
+    assert_eq!((Some(3), None), m.insert("bob", 3));
+    assert_eq!(2, m.len());
+    assert_consistent(&m);
+}
+
+#[test]
+fn insert_no_overwrite() {
+    let mut m = TSTBiMap::new();
+
+    assert_eq!(Ok(()), m.insert_no_overwrite("alice", 1));
+    assert_eq!(Ok(()), m.insert_no_overwrite("bob", 2));
+    assert_eq!(
+        Err(("alice".to_string(), 1)),
+        m.insert_no_overwrite("alice", 1)
+    );
+    assert_eq!(
+        Err(("carol".to_string(), 2)),
+        m.insert_no_overwrite("carol", 2)
+    );
+    assert_eq!(2, m.len());
+    assert_eq!(None, m.get_by_key("carol"));
+    assert_consistent(&m);
+}
+
+#[test]
+#[should_panic]
+fn insert_empty_key() {
+    let mut m = TSTBiMap::new();
+    m.insert("", 1);
+}
+
+#[test]
+fn remove() {
+    let mut m = TSTBiMap::new();
+    for (i, key) in ["alice", "bob", "carol"].iter().enumerate() {
+        m.insert(key, i as u32);
+    }
+
+    assert_eq!(Some(1), m.remove_by_key("bob"));
+    assert_eq!(None, m.remove_by_key("bob"));
+    assert_eq!(None, m.remove_by_value(&1));
+    assert_eq!(Some("carol".to_string()), m.remove_by_value(&2));
+    assert_eq!(None, m.get_by_key("carol"));
+    assert_eq!(1, m.len());
+    assert_consistent(&m);
+
+    assert_eq!(Some(0), m.remove_by_key("alice"));
+    assert!(m.is_empty());
+    assert_eq!(0, m.iter().count());
+}
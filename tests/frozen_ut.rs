@@ -0,0 +1,60 @@
+#[macro_use]
+extern crate tst;
+
+use self::tst::{FrozenTST, TSTMap};
+use std::sync::Arc;
+use std::thread;
+
+fn prepare_data() -> FrozenTST<i32> {
+    let m: TSTMap<i32> = tstmap! {
+        "BY" => 1,
+        "BYE" => 2,
+        "BYGONE" => 3,
+        "BYLAW" => 4,
+        "BYTE" => 5,
+    };
+    m.freeze()
+}
+
+#[test]
+fn read() {
+    let f = prepare_data();
+
+    assert_eq!(5, f.len());
+    assert!(!f.is_empty());
+    assert_eq!(Some(&4), f.get("BYLAW"));
+    assert_eq!(None, f.get("BYL"));
+    assert!(f.contains_key("BYTE"));
+    assert_eq!("BYTE", f.longest_prefix("BYTES"));
+    assert_eq!(
+        vec!["BYLAW"],
+        f.wildcard_iter("BY[LT]..")
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![&2, &3],
+        f.prefix_iter("BYE")
+            .chain(f.prefix_iter("BYG"))
+            .map(|(_, v)| v)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(15, f.values().sum::<i32>());
+    assert_eq!(5, f.keys().count());
+    assert_eq!(5, f.iter().count());
+}
+
+#[test]
+fn share_between_threads() {
+    let f = Arc::new(prepare_data());
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let f = f.clone();
+            thread::spawn(move || f.iter().map(|(_, v)| *v).sum::<i32>())
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(15, handle.join().unwrap());
+    }
+}
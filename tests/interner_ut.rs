@@ -0,0 +1,54 @@
+extern crate tst;
+
+use self::tst::Interner;
+
+#[test]
+fn intern() {
+    let mut i = Interner::new();
+
+    assert_eq!(0, i.intern("BY"));
+    assert_eq!(1, i.intern("BYE"));
+    assert_eq!(2, i.intern("B"));
+    assert_eq!(1, i.intern("BYE"));
+    assert_eq!(0, i.intern("BY"));
+    assert_eq!(3, i.len());
+}
+
+#[test]
+fn intern_empty() {
+    let mut i = Interner::new();
+
+    assert_eq!(0, i.intern("a"));
+    assert_eq!(1, i.intern(""));
+    assert_eq!(1, i.intern(""));
+    assert_eq!(Some(1), i.get(""));
+    assert_eq!(Some(""), i.resolve(1));
+    assert_eq!(2, i.len());
+}
+
+#[test]
+fn resolve() {
+    let mut i = Interner::new();
+    let words = ["слово", "word", "wordy", "w", "🦀"];
+    let ids: Vec<u32> = words.iter().map(|w| i.intern(w)).collect();
+
+    assert_eq!(vec![0, 1, 2, 3, 4], ids);
+    for (id, word) in ids.iter().zip(words.iter()) {
+        assert_eq!(Some(*word), i.resolve(*id));
+        assert_eq!(Some(*id), i.get(word));
+    }
+    assert_eq!(None, i.resolve(5));
+    assert_eq!(None, i.get("wor"));
+}
+
+#[test]
+fn clone() {
+    let mut i = Interner::new();
+    i.intern("a");
+    let mut j = i.clone();
+
+    assert_eq!(1, j.intern("b"));
+    assert_eq!(1, i.intern("c"));
+    assert_eq!(Some("b"), j.resolve(1));
+    assert_eq!(Some("c"), i.resolve(1));
+}
@@ -1,9 +1,18 @@
 #[macro_use]
 extern crate tst;
 
+use self::tst::bloom::Bloom;
 use self::tst::map::Entry::*;
+use self::tst::merge_sorted;
 use self::tst::TSTMap;
+use self::tst::ValueCodec;
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeSet;
+use std::io::{self, Read, Write};
 use std::iter::FromIterator;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
 
 fn prepare_data() -> TSTMap<i32> {
     tstmap! {
@@ -55,6 +64,75 @@ fn map_clone_strings_as_value() {
     assert_eq!(orig, cpy);
 }
 
+#[test]
+fn map_ord() {
+    let a = tstmap! { "abc" => 1, "abd" => 2 };
+    let b = tstmap! { "abc" => 1, "abd" => 3 };
+    let c = tstmap! { "abc" => 1 };
+    let d = tstmap! { "abd" => 0 };
+
+    assert!(a < b);
+    assert!(c < a);
+    assert!(b < d);
+    assert!(TSTMap::new() < c);
+    assert_eq!(Ordering::Equal, a.cmp(&a.clone()));
+
+    let set: BTreeSet<TSTMap<i32>> = vec![d.clone(), a.clone(), b.clone(), c.clone(), a.clone()]
+        .into_iter()
+        .collect();
+    assert_eq!(vec![&c, &a, &b, &d], set.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn map_partial_ord() {
+    let a = tstmap! { "abc" => 1.0 };
+    let b = tstmap! { "abc" => f64::NAN };
+    assert_eq!(None, a.partial_cmp(&b));
+    assert_eq!(
+        Some(Ordering::Less),
+        a.partial_cmp(&tstmap! { "abc" => 2.0 })
+    );
+}
+
+#[test]
+fn map_clone_from() {
+    let orig = prepare_data();
+    let mut cpy = tstmap! {
+        "BYE" => -2,
+        "first" => 1,
+        "firstthird" => 3,
+    };
+
+    cpy.clone_from(&orig);
+    assert_eq!(orig, cpy);
+    assert_eq!(13, cpy.len());
+    assert_eq!(None, cpy.get("first"));
+
+    cpy.clone_from(&TSTMap::new());
+    assert!(cpy.is_empty());
+    assert_eq!(None, cpy.get("BYE"));
+
+    cpy.clone_from(&orig);
+    cpy.insert("BYX", 100);
+    assert_eq!(Some(2), cpy.remove("BYE"));
+    assert_eq!(13, cpy.len());
+    assert_eq!(Some(&2), orig.get("BYE"));
+}
+
+#[test]
+fn map_clone_from_strings_as_value() {
+    let orig = tstmap! {
+        "first" => "1".to_string(),
+        "second" => "2".to_string(),
+    };
+    let mut cpy = tstmap! {
+        "first" => "100".to_string(),
+    };
+
+    cpy.clone_from(&orig);
+    assert_eq!(orig, cpy);
+}
+
 #[test]
 fn insert() {
     let mut m = TSTMap::<i32>::new();
@@ -101,6 +179,212 @@ fn get() {
     assert_eq!(Some(&13), m.get("abc"));
 }
 
+#[test]
+fn accessors_by_as_ref_str() {
+    let mut m = TSTMap::new();
+    let key = "abc".to_string();
+
+    assert_eq!(None, m.insert(key.clone(), 1));
+    assert_eq!(Some(1), m.insert(&key, 2));
+    assert_eq!(Some(&2), m.get(&key));
+    assert_eq!(Some(&2), m.get(Cow::Borrowed("abc")));
+    assert!(m.contains_key(key.clone()));
+    *m.get_mut(Cow::Owned::<str>(key.clone())).unwrap() += 1;
+    assert_eq!(Some(("abc".to_string(), 3)), m.remove_entry(&key));
+    assert_eq!(None, m.remove(key));
+}
+
+#[test]
+fn keys_without_normalization() {
+    let mut m = TSTMap::new();
+    m.insert("cafe\u{301}", 1);
+    assert_eq!(None, m.get("caf\u{e9}"));
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn keys_with_normalization() {
+    use self::tst::Normalization;
+
+    let mut m = TSTMap::with_normalization(Normalization::Nfd);
+    m.insert("caf\u{e9}", 1);
+    *m.entry("cafe\u{301}").or_insert(0) += 1;
+    assert_eq!("cafe\u{301}", m.entry_owned("caf\u{e9}".to_string()).key());
+    assert_eq!(1, m.len());
+    assert_eq!(Some(&2), m.get("caf\u{e9}"));
+    assert!(m.contains_key("cafe\u{301}"));
+    assert_eq!(vec!["cafe\u{301}"], m.keys().collect::<Vec<_>>());
+
+    let cloned = m.clone();
+    assert_eq!(Some(&2), cloned.get("caf\u{e9}"));
+
+    if let Occupied(entry) = m.entry("caf\u{e9}") {
+        entry.move_to("caf\u{e9}s");
+    }
+    assert_eq!(vec!["cafe\u{301}s"], m.keys().collect::<Vec<_>>());
+    if let Occupied(entry) = m.entry("caf\u{e9}s") {
+        entry.move_to("caf\u{e9}");
+    }
+
+    *m.get_mut("caf\u{e9}").unwrap() += 1;
+    assert_eq!(3, m["cafe\u{301}"]);
+    assert_eq!(
+        Some(("cafe\u{301}".to_string(), 3)),
+        m.remove_entry("caf\u{e9}")
+    );
+    assert!(m.is_empty());
+
+    let mut m = TSTMap::with_normalization(Normalization::Nfkc);
+    m.insert("\u{fb01}le", 1);
+    assert_eq!(Some(&1), m.get("file"));
+    assert_eq!(Some(1), m.remove("\u{fb01}le"));
+
+    m.insert("\u{fb01}le", 1);
+    m.clear();
+    m.insert("\u{fb01}le", 2);
+    assert_eq!(Some(&2), m.get("file"));
+}
+
+// keys in NFC, which tests look up in NFD
+#[cfg(feature = "unicode-normalization")]
+fn nfc_data() -> TSTMap<i32> {
+    let mut m = TSTMap::with_normalization(self::tst::Normalization::Nfc);
+    m.insert("caf\u{e9}", 1);
+    m.insert("caf\u{e9} au lait", 2);
+    m.insert("cr\u{e8}me", 3);
+    m
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn count_prefix_with_normalization() {
+    assert_eq!(2, nfc_data().count_prefix("cafe\u{301}"));
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn prefix_iter_with_normalization() {
+    let mut m = nfc_data();
+    let mut keys: Vec<_> = m.prefix_keys("cafe\u{301}").collect();
+    keys.sort();
+    assert_eq!(vec!["caf\u{e9}", "caf\u{e9} au lait"], keys);
+    assert_eq!(2, m.prefix_iter("cafe\u{301}").count());
+    let mut completions: Vec<_> = m.completions("cafe\u{301}").map(|(k, _)| k).collect();
+    completions.sort();
+    assert_eq!(vec!["", " au lait"], completions);
+    for (_, value) in m.prefix_iter_mut("cafe\u{301}") {
+        *value += 10;
+    }
+    assert_eq!(Some(&12), m.get("caf\u{e9} au lait"));
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn rank_with_normalization() {
+    let m = nfc_data();
+    assert_eq!(1, m.rank("cafe\u{301} au lait"));
+    assert_eq!(2, m.rank("cre\u{300}me"));
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn clear_prefix_with_normalization() {
+    let mut m = nfc_data();
+    assert_eq!(1, m.clear_children("cafe\u{301}"));
+    assert_eq!(1, m.clear_prefix("cafe\u{301}"));
+    assert_eq!(vec!["cr\u{e8}me"], m.keys().collect::<Vec<_>>());
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn longest_prefix_with_normalization() {
+    let m = nfc_data();
+    // the result is the matching part of the query as it was given
+    assert_eq!("cafe\u{301}", m.longest_prefix("cafe\u{301} noir"));
+    assert_eq!("caf\u{e9}", m.longest_prefix("caf\u{e9} noir"));
+    assert_eq!("", m.longest_prefix("cafe"));
+    assert_eq!(4 + 1, m.longest_prefix_len("cafe\u{301} noir".chars()));
+    assert_eq!(
+        vec![("cafe\u{301} au lait", &2), ("cafe\u{301}", &1)],
+        m.matching_prefixes_longest_first("cafe\u{301} au lait!")
+            .collect::<Vec<_>>()
+    );
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn get_many_with_normalization() {
+    let mut m = nfc_data();
+    assert_eq!(
+        vec![Some(&1), Some(&3)],
+        m.get_many(&["cafe\u{301}", "cre\u{300}me"])
+    );
+    for value in m.get_many_mut(&["cafe\u{301}", "cre\u{300}me"]).unwrap() {
+        *value *= 10;
+    }
+    assert_eq!(10, m["cafe\u{301}"]);
+    m["cre\u{300}me"] += 1;
+    assert_eq!(31, m["cr\u{e8}me"]);
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn iter_from_with_normalization() {
+    let m = nfc_data();
+    assert_eq!(
+        vec!["caf\u{e9} au lait", "cr\u{e8}me"],
+        m.iter_from("cafe\u{301} ")
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>()
+    );
+    let mut iter = m.iter();
+    iter.advance_to("cre\u{300}");
+    assert_eq!(Some("cr\u{e8}me".to_string()), iter.next().map(|(k, _)| k));
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn merge_with_normalization() {
+    let mut m = nfc_data();
+    let mut other = TSTMap::new();
+    other.insert("cafe\u{301}", 10);
+    other.insert("the\u{301}", 20);
+    m.merge_with(other, |existing, incoming| *existing += incoming);
+    assert_eq!(4, m.len());
+    assert_eq!(Some(&11), m.get("caf\u{e9}"));
+    assert_eq!(Some(&20), m.get("th\u{e9}"));
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn fuzzy_with_normalization() {
+    let m = nfc_data();
+    assert_eq!(
+        vec!["cr\u{e8}me"],
+        m.fuzzy_prefix_iter("cre\u{300}m", 0)
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["caf\u{e9}"],
+        m.damerau_iter("cafe\u{301}", 0)
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![("cr\u{e8}me".to_string(), 0)],
+        m.suggest("cre\u{300}me", 0, 5)
+    );
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn divergence_point_with_normalization() {
+    let m = nfc_data();
+    assert_eq!(4, m.divergence_point("cafe\u{301}s", "cafe\u{301}x"));
+    assert_eq!(2, m.keys_between_prefix("cafe\u{301}s", "cafe\u{301}x"));
+}
+
 #[test]
 fn get_none() {
     let mut m = TSTMap::new();
@@ -135,6 +419,97 @@ fn get_mut_none() {
     assert_eq!(None, m.get_mut("abx"));
 }
 
+#[test]
+fn get_many() {
+    let m = prepare_data();
+
+    assert_eq!(
+        vec![Some(&1), None, Some(&1), Some(&13)],
+        m.get_many(&["BY", "BYX", "BY", "BYWORD"])
+    );
+    assert!(m.get_many(&[]).is_empty());
+}
+
+#[test]
+fn get_many_mut() {
+    let mut m = prepare_data();
+
+    {
+        let values = m.get_many_mut(&["BYWORD", "BY", "BYE"]).unwrap();
+        assert_eq!(vec![&mut 13, &mut 1, &mut 2], values);
+        for v in values {
+            *v *= -1;
+        }
+    }
+    assert_eq!(-13, m["BYWORD"]);
+    assert_eq!(-1, m["BY"]);
+    assert_eq!(-2, m["BYE"]);
+    assert_eq!(Some(vec![]), m.get_many_mut(&[]));
+}
+
+#[test]
+fn get_many_mut_none() {
+    let mut m = prepare_data();
+
+    assert_eq!(None, m.get_many_mut(&["BYE", "BY", "BYE"]));
+    assert_eq!(None, m.get_many_mut(&["BYE", "BYX"]));
+    assert_eq!(None, m.get_many_mut(&["B"]));
+}
+
+#[test]
+fn contains_key_with_bloom() {
+    let mut m = TSTMap::with_bloom(100);
+    for i in 0..100 {
+        m.insert(format!("key{}", i), i);
+    }
+    *m.entry("entry").or_insert(0) += 1;
+    m.get_or_insert_with("get", || 0);
+    m.cursor("cursor:").insert_suffix("a", 0);
+    if let Occupied(entry) = m.entry("key0") {
+        entry.move_to("moved");
+    }
+    let other = tstmap! { "merged" => 0 };
+    m.merge_with(other, |_, _| {});
+    for key in ["entry", "get", "cursor:a", "moved", "merged", "key99"] {
+        assert!(m.contains_key(key), "{}", key);
+    }
+
+    assert!((0..1000).all(|i| !m.contains_key(format!("missing{}", i))));
+    assert!(!m.contains_key("key0"));
+
+    m.remove("key1");
+    assert!(!m.contains_key("key1"));
+
+    let cloned = m.clone();
+    assert!(cloned.contains_key("key2"));
+
+    m.clear();
+    assert!(!m.contains_key("key2"));
+    m.insert("key2", 2);
+    assert!(m.contains_key("key2"));
+}
+
+#[test]
+fn with_bloom_huge_expected_keys() {
+    let mut m = TSTMap::with_bloom(usize::MAX);
+    m.insert("key", 1);
+    assert!(m.contains_key("key"));
+    assert!(!m.contains_key("missing"));
+}
+
+#[test]
+fn bloom_false_positives() {
+    let mut bloom = Bloom::new(1000);
+    for i in 0..1000 {
+        bloom.insert(&format!("key{}", i));
+    }
+    assert!((0..1000).all(|i| bloom.contains(&format!("key{}", i))));
+    let false_positives = (0..10000)
+        .filter(|i| bloom.contains(&format!("missing{}", i)))
+        .count();
+    assert!(false_positives < 300, "{}", false_positives);
+}
+
 #[test]
 fn entry_occupied() {
     let mut m = TSTMap::new();
@@ -167,6 +542,135 @@ fn entry_occupied_remove() {
     assert_eq!(1, m.len());
 }
 
+#[test]
+fn entry_key() {
+    let mut m = prepare_data();
+
+    assert_eq!("BYE", m.entry("BYE").key());
+    assert_eq!("BYES", m.entry("BYES").key());
+    match m.entry("BYES") {
+        Vacant(entry) => assert_eq!("BYES", entry.into_key()),
+        Occupied(_) => unreachable!(),
+    }
+    assert_eq!(13, m.len());
+}
+
+#[test]
+fn entry_owned() {
+    let mut m = prepare_data();
+
+    let key = String::from("BYE");
+    assert_eq!("BYE", m.entry_owned(key).key());
+    match m.entry_owned(format!("{}S", "BYE")) {
+        Vacant(entry) => {
+            assert_eq!("BYES", entry.key());
+            entry.insert(14);
+        }
+        Occupied(_) => unreachable!(),
+    }
+    match m.entry_owned("BYES".to_string()) {
+        Occupied(entry) => assert_eq!(("BYES".to_string(), 14), entry.remove_entry()),
+        Vacant(_) => unreachable!(),
+    }
+    assert_eq!(13, m.len());
+}
+
+#[test]
+#[should_panic]
+fn entry_owned_empty_key() {
+    let mut m = prepare_data();
+    m.entry_owned(String::new());
+}
+
+#[test]
+fn entry_has_descendants() {
+    let mut m = TSTMap::new();
+    m.insert("a/b/c", 1);
+    m.insert("a/d", 2);
+
+    assert!(m.entry("a/b").has_descendants());
+    assert!(m.entry("a").has_descendants());
+    assert!(!m.entry("a/d").has_descendants());
+    assert!(!m.entry("a/b/c/").has_descendants());
+    assert!(!m.entry("x").has_descendants());
+
+    match m.entry("a/b") {
+        Vacant(entry) => assert!(entry.has_descendants()),
+        Occupied(_) => unreachable!(),
+    }
+    match m.entry("a/d") {
+        Occupied(entry) => assert!(!entry.has_descendants()),
+        Vacant(_) => unreachable!(),
+    }
+
+    m.remove("a/b/c");
+    assert!(!m.entry("a/b").has_descendants());
+}
+
+#[test]
+fn entry_or_insert_with_key() {
+    let mut m = TSTMap::new();
+    for word in ["слово", "word", "wordy", "word"] {
+        *m.entry(word).or_insert_with_key(|k| k.chars().count()) += 1;
+    }
+
+    assert_eq!(3, m.len());
+    assert_eq!(Some(&6), m.get("слово"));
+    assert_eq!(Some(&6), m.get("word"));
+    assert_eq!(Some(&6), m.get("wordy"));
+}
+
+#[test]
+fn entry_or_try_insert_with() {
+    let mut m = prepare_data();
+
+    let res: Result<&mut i32, std::num::ParseIntError> =
+        m.entry("BYTES").or_try_insert_with(|| "x".parse());
+    assert!(res.is_err());
+    assert_eq!(13, m.len());
+    assert_eq!(None, m.get("BYTES"));
+    assert!(!m.entry("BYTE").has_descendants());
+    assert_eq!(Ok(()), m.validate());
+
+    assert_eq!(
+        Ok(&mut 14),
+        m.entry("BYTES").or_try_insert_with(|| "14".parse::<i32>())
+    );
+    assert_eq!(14, m.len());
+
+    let res: Result<&mut i32, ()> = m.entry("BYTE").or_try_insert_with(|| panic!());
+    assert_eq!(Ok(&mut 11), res);
+    assert_eq!(14, m.len());
+}
+
+#[test]
+fn entry_modify_and_get() {
+    let mut m = prepare_data();
+
+    assert_eq!(&12, m.entry("BYTE").modify_and_get(0, |v| *v += 1));
+    assert_eq!(&1, m.entry("BYTES").modify_and_get(0, |v| *v += 1));
+    assert_eq!(&2, m.entry("BYTES").modify_and_get(0, |v| *v += 1));
+    assert_eq!(14, m.len());
+    assert_eq!(Some(&2), m.get("BYTES"));
+}
+
+#[test]
+fn entry_occupied_remove_entry() {
+    let mut m = TSTMap::new();
+
+    m.insert("abcde", 13);
+    m.insert("abcdf", 14);
+    match m.entry("abcdf") {
+        Vacant(_) => unreachable!(),
+        Occupied(entry) => {
+            assert_eq!("abcdf", entry.key());
+            assert_eq!(("abcdf".to_string(), 14), entry.remove_entry());
+        }
+    }
+    assert_eq!(None, m.get("abcdf"));
+    assert_eq!(1, m.len());
+}
+
 #[test]
 fn entry_occupied_update() {
     let mut m = TSTMap::new();
@@ -192,6 +696,36 @@ fn entry_occupied_update() {
     assert_eq!(2, m.len());
 }
 
+#[test]
+fn entry_occupied_move_to() {
+    let mut m = prepare_data();
+
+    match m.entry("BYE") {
+        Occupied(entry) => assert_eq!(None, entry.move_to("BYEBYE")),
+        Vacant(_) => unreachable!(),
+    }
+    assert_eq!(None, m.get("BYE"));
+    assert_eq!(Some(&2), m.get("BYEBYE"));
+    assert_eq!(13, m.len());
+    assert_eq!(Ok(()), m.validate());
+
+    match m.entry("BYWAY") {
+        Occupied(entry) => assert_eq!(Some(13), entry.move_to("BYWORD")),
+        Vacant(_) => unreachable!(),
+    }
+    assert_eq!(Some(&12), m.get("BYWORD"));
+    assert_eq!(12, m.len());
+    assert_eq!(Ok(()), m.validate());
+
+    match m.entry("BY") {
+        Occupied(entry) => assert_eq!(None, entry.move_to("BY")),
+        Vacant(_) => unreachable!(),
+    }
+    assert_eq!(Some(&1), m.get("BY"));
+    assert_eq!(12, m.len());
+    assert_eq!(Ok(()), m.validate());
+}
+
 #[test]
 fn entry_vacant() {
     let mut m = TSTMap::new();
@@ -207,49 +741,307 @@ fn entry_vacant() {
 }
 
 #[test]
-fn insert_few() {
-    let mut m = TSTMap::new();
-
-    m.insert("abcde", 13);
-    m.insert("abcdf", 14);
-    m.insert("abcdg", 15);
-    assert_eq!(3, m.len());
+fn cursor() {
+    let mut m = prepare_data();
 
-    assert_eq!(Some(&13), m.get("abcde"));
-    assert_eq!(Some(&14), m.get("abcdf"));
-    assert_eq!(Some(&15), m.get("abcdg"));
-    assert_eq!(None, m.get("abcdh"));
+    let mut cursor = m.cursor("BYP");
+    assert_eq!("BYP", cursor.prefix());
+    assert_eq!(Some(&6), cursor.get_suffix("ASS"));
+    assert_eq!(None, cursor.get_suffix(""));
+    assert_eq!(Some(6), cursor.insert_suffix("ASS", 60));
+    assert_eq!(None, cursor.insert_suffix("LAY", 9));
+    assert_eq!(None, cursor.insert_suffix("", 10));
+    assert_eq!(Some(&10), cursor.get_suffix(""));
+
+    assert_eq!(Some(&60), m.get("BYPASS"));
+    assert_eq!(Some(&9), m.get("BYPLAY"));
+    assert_eq!(Some(&10), m.get("BYP"));
+    assert_eq!(15, m.len());
+    assert_eq!(5, m.count_prefix("BYP"));
+    assert_eq!(Ok(()), m.validate());
+
+    let mut cursor = m.cursor("");
+    assert_eq!(None, cursor.insert_suffix("A", 0));
+    assert_eq!(Some(&1), cursor.get_suffix("BY"));
+    assert_eq!(16, m.len());
+    assert_eq!(Ok(()), m.validate());
+
+    let mut cursor = m.cursor("user:1:");
+    for (suffix, value) in [("name", 1), ("age", 2), ("email", 3)] {
+        cursor.insert_suffix(suffix, value);
+    }
+    assert_eq!(
+        vec!["user:1:age", "user:1:email", "user:1:name"],
+        m.prefix_keys("user:1:").collect::<Vec<_>>()
+    );
+    assert_eq!(19, m.len());
+    assert_eq!(Ok(()), m.validate());
 }
 
 #[test]
-fn replace() {
-    let mut m = TSTMap::<i32>::new();
-
-    m.insert("abcde", 13);
-    m.insert("abcde", 1);
-    assert_eq!(1, m.len());
-
-    assert_eq!(Some(&1), m.get("abcde"));
+fn cursor_creates_nodes_on_insert() {
+    let mut m = prepare_data();
+    let nodes = m.stats().nodes;
+
+    let cursor = m.cursor("BYX");
+    assert_eq!(None, cursor.get_suffix("Y"));
+    assert_eq!(None, cursor.get_suffix(""));
+    assert_eq!(nodes, m.stats().nodes);
+    assert_eq!("BY", m.common_prefix());
+
+    let mut cursor = m.cursor("BYX");
+    assert_eq!(None, cursor.insert_suffix("", 1));
+    assert_eq!(None, cursor.insert_suffix("Y", 2));
+    assert_eq!(Some(&2), cursor.get_suffix("Y"));
+    assert_eq!(nodes + 2, m.stats().nodes);
+    assert_eq!(Some(&1), m.get("BYX"));
+    assert_eq!(Ok(()), m.validate());
 }
 
 #[test]
-fn contains() {
-    let mut m = TSTMap::<i32>::new();
-
-    m.insert("xxxe", 13);
-    assert!(!m.contains_key("abcde"));
-    assert!(!m.contains_key("xxx"));
-    assert!(m.contains_key("xxxe"));
+#[should_panic]
+fn cursor_empty_key() {
+    let mut m = TSTMap::new();
+    m.cursor("").insert_suffix("", 1);
 }
 
 #[test]
-fn is_empty() {
-    let mut m = TSTMap::<u32>::new();
-
-    assert_eq!(0, m.len());
-    assert!(m.is_empty());
+fn drain_filter() {
+    let mut m = prepare_data();
 
-    m.insert("qwer", 10_000);
+    let removed: Vec<(String, i32)> = m.drain_filter(|_, v| *v % 2 == 0).collect();
+    assert_eq!(
+        vec![2, 4, 6, 8, 10, 12],
+        removed.iter().map(|(_, v)| *v).collect::<Vec<_>>()
+    );
+    for (key, _) in &removed {
+        assert_eq!(None, m.get(key));
+    }
+    assert_eq!(7, m.len());
+    assert_eq!(Ok(()), m.validate());
+
+    // values of kept entries may be changed by the predicate
+    assert_eq!(
+        0,
+        m.drain_filter(|_, v| {
+            *v *= 10;
+            false
+        })
+        .count()
+    );
+    assert_eq!(Some(&10), m.get("BY"));
+
+    // only yielded entries are removed
+    let mut iter = m.drain_filter(|k, _| k.starts_with("BYL"));
+    assert_eq!(Some(("BYLINE".to_string(), 50)), iter.next());
+    drop(iter);
+    assert_eq!(6, m.len());
+    assert_eq!(Ok(()), m.validate());
+
+    assert_eq!(6, m.drain_filter(|_, _| true).count());
+    assert!(m.is_empty());
+    assert_eq!(0, m.stats().nodes);
+}
+
+#[test]
+fn insert_mut() {
+    let mut m = TSTMap::new();
+
+    m.insert_mut("BY", vec![]).push(1);
+    m.insert_mut("BYE", vec![2]).push(3);
+    m.insert_mut("BY", vec![4]).push(5);
+    assert_eq!(2, m.len());
+    assert_eq!(Some(&vec![4, 5]), m.get("BY"));
+    assert_eq!(Some(&vec![2, 3]), m.get("BYE"));
+    assert_eq!(Ok(()), m.validate());
+
+    let mut m = TSTMap::with_max_entries(1);
+    *m.insert_mut("a", 1) += 1;
+    *m.insert_mut("b", 3) += 1;
+    assert_eq!(vec![("b".to_string(), &4)], m.iter().collect::<Vec<_>>());
+}
+
+#[test]
+#[should_panic]
+fn insert_mut_empty_key() {
+    let mut m = TSTMap::new();
+    m.insert_mut("", 1);
+}
+
+#[test]
+fn insert_reporting_depth() {
+    let mut m = TSTMap::new();
+
+    // sorted inserts make a list of side steps
+    for (i, key) in ["a", "b", "c", "d", "e"].iter().enumerate() {
+        assert_eq!((None, i), m.insert_reporting_depth(key, i));
+    }
+    assert_eq!((None, 4), m.insert_reporting_depth("ez", 5));
+    assert_eq!((Some(5), 4), m.insert_reporting_depth("ez", 6));
+    assert_eq!((None, 0), m.insert_reporting_depth("aж", 7));
+    assert_eq!(7, m.len());
+    assert_eq!(Ok(()), m.validate());
+
+    let mut m = TSTMap::new();
+    for key in ["d", "b", "f", "a", "c", "e", "g"] {
+        assert!(m.insert_reporting_depth(key, ()).1 <= 2);
+    }
+}
+
+#[test]
+fn entry_long_key() {
+    let mut m = TSTMap::new();
+    let key = "ж".repeat(5000);
+
+    *m.entry(&key).or_insert(0) += 1;
+    *m.entry(&key).or_insert(0) += 1;
+    assert_eq!(Some(&2), m.get(&key));
+    assert_eq!(None, m.insert(&key[..2], 1));
+    assert_eq!(2, m.len());
+    assert_eq!(Ok(()), m.validate());
+
+    match m.entry(&key) {
+        Occupied(entry) => assert_eq!(2, entry.remove()),
+        Vacant(_) => unreachable!(),
+    }
+    assert_eq!(1, m.len());
+    assert_eq!(Ok(()), m.validate());
+}
+
+#[test]
+fn entry_vacant_dropped() {
+    let mut m = prepare_data();
+    let nodes = m.stats().nodes;
+
+    for key in ["BYX", "BYGONER", "A", "BYG"] {
+        match m.entry(key) {
+            Vacant(entry) => assert_eq!(key, entry.key()),
+            Occupied(_) => unreachable!(),
+        }
+    }
+    assert_eq!(nodes, m.stats().nodes);
+    assert_eq!(13, m.len());
+    assert_eq!(Ok(()), m.validate());
+
+    assert!(m.entry("BYG").has_descendants());
+    assert!(!m.entry("BYX").has_descendants());
+    m.entry("BYG").or_insert(0);
+    assert_eq!(nodes, m.stats().nodes);
+    m.entry("BYX").or_insert(0);
+    assert_eq!(nodes + 1, m.stats().nodes);
+    assert_eq!(15, m.len());
+    assert_eq!(Ok(()), m.validate());
+}
+
+#[test]
+fn entry_reinsert_after_remove() {
+    let mut m = prepare_data();
+    let nodes = m.stats().nodes;
+
+    // the nodes of a removed key stay, when it's removed through its entry
+    for round in 0..3 {
+        match m.entry("BYLAW") {
+            Occupied(entry) => entry.remove(),
+            Vacant(_) => unreachable!(),
+        };
+        assert_eq!(12, m.len());
+        assert_eq!(nodes, m.stats().nodes);
+        assert_eq!(Ok(()), m.validate());
+
+        assert_eq!(&round, m.entry("BYLAW").or_insert(round));
+        assert_eq!(13, m.len());
+        assert_eq!(nodes, m.stats().nodes);
+        assert_eq!(Ok(()), m.validate());
+    }
+
+    // the key with a value in the middle of the path
+    assert_eq!(Some(2), m.remove("BYE"));
+    assert_eq!(12, m.len());
+    assert_eq!(&5, m.entry("BYE").or_insert(5));
+    assert_eq!(&5, m.entry("BYE").or_insert(6));
+    assert_eq!(13, m.len());
+    assert_eq!(Ok(()), m.validate());
+
+    // the last key, so the whole tree is pruned
+    let mut m = TSTMap::new();
+    for round in 0..3 {
+        assert_eq!(&round, m.entry("key").or_insert(round));
+        assert_eq!(1, m.len());
+        assert_eq!(Some(round), m.remove("key"));
+        assert_eq!(0, m.len());
+        assert_eq!(Ok(()), m.validate());
+    }
+    assert_eq!(&1, m.entry("k").or_insert(1));
+    assert_eq!(&2, m.entry("key").or_insert(2));
+    assert_eq!(2, m.len());
+    assert_eq!(Ok(()), m.validate());
+}
+
+#[test]
+fn get_or_insert_with() {
+    let mut m = prepare_data();
+
+    *m.get_or_insert_with("BYTE", || unreachable!()) += 1;
+    assert_eq!(12, m["BYTE"]);
+    assert_eq!(13, m.len());
+
+    *m.get_or_insert_with("BYTES", || 100) += 1;
+    assert_eq!(101, m["BYTES"]);
+    assert_eq!(14, m.len());
+    assert_eq!(2, m.count_prefix("BYT"));
+}
+
+#[test]
+#[should_panic]
+fn get_or_insert_with_empty_key() {
+    let mut m = TSTMap::new();
+    m.get_or_insert_with("", || 1);
+}
+
+#[test]
+fn insert_few() {
+    let mut m = TSTMap::new();
+
+    m.insert("abcde", 13);
+    m.insert("abcdf", 14);
+    m.insert("abcdg", 15);
+    assert_eq!(3, m.len());
+
+    assert_eq!(Some(&13), m.get("abcde"));
+    assert_eq!(Some(&14), m.get("abcdf"));
+    assert_eq!(Some(&15), m.get("abcdg"));
+    assert_eq!(None, m.get("abcdh"));
+}
+
+#[test]
+fn replace() {
+    let mut m = TSTMap::<i32>::new();
+
+    m.insert("abcde", 13);
+    m.insert("abcde", 1);
+    assert_eq!(1, m.len());
+
+    assert_eq!(Some(&1), m.get("abcde"));
+}
+
+#[test]
+fn contains() {
+    let mut m = TSTMap::<i32>::new();
+
+    m.insert("xxxe", 13);
+    assert!(!m.contains_key("abcde"));
+    assert!(!m.contains_key("xxx"));
+    assert!(m.contains_key("xxxe"));
+}
+
+#[test]
+fn is_empty() {
+    let mut m = TSTMap::<u32>::new();
+
+    assert_eq!(0, m.len());
+    assert!(m.is_empty());
+
+    m.insert("qwer", 10_000);
     assert!(!m.is_empty());
 
     m.clear();
@@ -298,6 +1090,64 @@ fn remove() {
     assert_eq!(0, m.len());
 }
 
+#[test]
+fn rename() {
+    let mut m = prepare_data();
+
+    assert!(m.rename("BYE", "BYEBYE"));
+    assert_eq!(13, m.len());
+    assert_eq!(None, m.get("BYE"));
+    assert_eq!(Some(&2), m.get("BYEBYE"));
+
+    assert!(m.rename("BYTE", "BY"));
+    assert_eq!(12, m.len());
+    assert_eq!(Some(&11), m.get("BY"));
+
+    assert!(m.rename("BY", "BY"));
+    assert_eq!(12, m.len());
+    assert_eq!(Some(&11), m.get("BY"));
+
+    assert!(!m.rename("BYTE", "BYWAY"));
+    assert_eq!(12, m.len());
+    assert_eq!(Some(&12), m.get("BYWAY"));
+}
+
+#[test]
+#[should_panic]
+fn rename_to_empty() {
+    let mut m = prepare_data();
+    m.rename("BYE", "");
+}
+
+#[test]
+fn remove_if() {
+    let mut m = prepare_data();
+
+    assert_eq!(None, m.remove_if("BYE", |v| *v == 3));
+    assert_eq!(13, m.len());
+    assert_eq!(Some(&2), m.get("BYE"));
+
+    assert_eq!(Some(2), m.remove_if("BYE", |v| *v == 2));
+    assert_eq!(12, m.len());
+    assert_eq!(None, m.get("BYE"));
+
+    assert_eq!(None, m.remove_if("BYE", |_| true));
+    assert_eq!(None, m.remove_if("BYX", |_| unreachable!()));
+    assert_eq!(None, m.remove_if("B", |_| unreachable!()));
+    assert_eq!(12, m.len());
+}
+
+#[test]
+fn remove_entry() {
+    let mut m = tstmap!["abc" => 1, "abcd" => 2];
+
+    assert_eq!(Some(("abc".to_string(), 1)), m.remove_entry("abc"));
+    assert_eq!(None, m.remove_entry("abc"));
+    assert_eq!(None, m.remove_entry("ab"));
+    assert_eq!(Some(&2), m.get("abcd"));
+    assert_eq!(1, m.len());
+}
+
 #[test]
 fn remove_rich() {
     let mut m = prepare_data();
@@ -322,126 +1172,1600 @@ fn remove_only_tail() {
 }
 
 #[test]
-fn longest_prefix_empty() {
-    let mut m = TSTMap::new();
-    m.insert("abc", 1);
+fn remove_and_report() {
+    let mut m = prepare_data();
+    let mut nodes = m.stats().nodes;
 
-    assert_eq!("", m.longest_prefix("qwer"));
-    assert_eq!("", m.longest_prefix(""));
-}
+    for (key, value) in [("BYPRODUCT", 8), ("BYE", 2), ("BYPASS", 6), ("BYPATH", 7)] {
+        let (removed, detached) = m.remove_and_report(key).unwrap();
+        assert_eq!(value, removed);
+        assert_eq!(nodes - detached, m.stats().nodes);
+        nodes -= detached;
+    }
+    assert_eq!(None, m.remove_and_report("BYPATH"));
+    assert_eq!(None, m.remove_and_report("BYW"));
+    assert_eq!(Some((1, 0)), m.remove_and_report("BY"));
+    assert_eq!(8, m.len());
+    assert_eq!(nodes, m.stats().nodes);
 
-#[test]
-fn longest_prefix() {
     let mut m = TSTMap::new();
     m.insert("abc", 1);
-    m.insert("abcd", 1);
-    m.insert("abce", 1);
-    m.insert("abca", 1);
-    m.insert("zxd", 1);
-    m.insert("add", 1);
-    m.insert("abcdef", 1);
-
-    assert_eq!("abcd", m.longest_prefix("abcde"));
-    assert_eq!("abcdef", m.longest_prefix("abcdef"));
+    assert_eq!(Some((1, 3)), m.remove_and_report("abc"));
+    assert_eq!(0, m.stats().nodes);
 }
 
 #[test]
-fn access_by_index() {
-    let mut m = TSTMap::new();
+fn soft_remove() {
+    let mut m = prepare_data();
+    let nodes = m.stats().nodes;
 
-    m.insert("abc", 2);
-    m.insert("abd", 1);
-    m.insert("abdd", 4);
+    assert_eq!(Some(8), m.soft_remove("BYPRODUCT"));
+    assert_eq!(None, m.soft_remove("BYPRODUCT"));
+    assert_eq!(None, m.soft_remove("BYPROD"));
+    assert_eq!(12, m.len());
+    assert_eq!(None, m.get("BYPRODUCT"));
+    assert_eq!(nodes, m.stats().nodes);
+    assert_eq!(2, m.count_prefix("BYP"));
+
+    assert_eq!(None, m.insert("BYPRODUCT", 80));
+    assert_eq!(nodes, m.stats().nodes);
+    assert_eq!(13, m.len());
+    assert_eq!(3, m.count_prefix("BYP"));
+}
 
-    assert_eq!(2, m["abc"]);
-    assert_eq!(1, m["abd"]);
-    assert_eq!(4, m["abdd"]);
+#[test]
+fn purge_tombstones() {
+    let mut m = prepare_data();
+    let nodes = m.stats().nodes;
+
+    m.soft_remove("BYPRODUCT");
+    m.soft_remove("BY");
+    m.entry("BYX");
+    m.purge_tombstones();
+    assert_eq!(nodes - 6, m.stats().nodes);
+    assert_eq!(11, m.len());
+    assert_eq!(Some(&6), m.get("BYPASS"));
+
+    m.clear_prefix("B");
+    m.purge_tombstones();
+    assert_eq!(0, m.stats().nodes);
+    assert!(m.is_empty());
 }
 
 #[test]
-fn access_by_index_mut() {
-    let mut m = TSTMap::new();
+fn clear_prefix() {
+    let mut m = prepare_data();
 
-    m.insert("abc", 2);
-    m.insert("abd", 1);
-    m.insert("abdd", 4);
-    {
-        let v = &mut m["abc"];
-        *v += 1;
-    }
+    assert_eq!(2, m.clear_prefix("BYPA"));
+    assert_eq!(None, m.get("BYPASS"));
+    assert_eq!(None, m.get("BYPATH"));
+    assert_eq!(Some(&8), m.get("BYPRODUCT"));
+    assert_eq!(11, m.len());
 
-    assert_eq!(3, m["abc"]);
-    assert_eq!(1, m["abd"]);
-    assert_eq!(4, m["abdd"]);
+    assert_eq!(11, m.clear_prefix("BY"));
+    assert!(m.is_empty());
 }
 
 #[test]
-#[should_panic]
-fn access_by_wrong_index() {
-    let mut m = TSTMap::new();
+fn clear_prefix_non_existing() {
+    let mut m = prepare_data();
 
-    m.insert("abc", 2);
-    m.insert("abd", 1);
-    m.insert("abdd", 4);
+    assert_eq!(0, m.clear_prefix("BYX"));
+    assert_eq!(0, m.clear_prefix("BYPRODUCTS"));
+    assert_eq!(13, m.len());
+    assert_eq!(13, m.clear_prefix(""));
+    assert!(m.is_empty());
+}
 
-    assert_eq!(3, m["a"]);
+#[test]
+fn clear_children() {
+    let mut m = prepare_data();
+    let nodes = m.stats().nodes;
+
+    assert_eq!(0, m.clear_children("BYTE"));
+    assert_eq!(0, m.clear_children("BYX"));
+    assert_eq!(13, m.len());
+    assert_eq!(nodes, m.stats().nodes);
+
+    assert_eq!(0, m.clear_children("BYE"));
+    assert_eq!(None, m.insert("BYEBYE", 14));
+    assert_eq!(1, m.clear_children("BYE"));
+    assert_eq!(Some(&2), m.get("BYE"));
+    assert_eq!(13, m.len());
+    assert_eq!(nodes, m.stats().nodes);
+    assert_eq!(Ok(()), m.validate());
+
+    // the dead nodes of the path are pruned, when there is no prefix key
+    assert_eq!(2, m.clear_children("BYPA"));
+    assert_eq!(Some(&8), m.get("BYPRODUCT"));
+    assert_eq!(11, m.len());
+    assert_eq!(nodes - 4, m.stats().nodes);
+    assert_eq!(Ok(()), m.validate());
+
+    assert_eq!(10, m.clear_children("BY"));
+    assert_eq!(vec!["BY"], m.keys().collect::<Vec<_>>());
+    assert_eq!(2, m.stats().nodes);
+    assert_eq!(Ok(()), m.validate());
+
+    assert_eq!(1, m.clear_children(""));
+    assert!(m.is_empty());
+}
+
+fn assert_ordinals(m: &TSTMap<i32>) {
+    let entries: Vec<_> = m.iter().collect();
+    for (n, (key, value)) in entries.iter().enumerate() {
+        assert_eq!(Some((key.clone(), *value)), m.nth(n));
+        assert_eq!(n, m.rank(key));
+        let count = entries
+            .iter()
+            .filter(|(k, _)| k.starts_with(key.as_str()))
+            .count();
+        assert_eq!(count, m.count_prefix(key));
+    }
+    assert_eq!(None, m.nth(entries.len()));
 }
 
 #[test]
-fn format_empty() {
-    let m = TSTMap::<u64>::new();
+fn count_prefix() {
+    let m = prepare_data();
 
-    assert_eq!("{}", format!("{:?}", m));
+    assert_eq!(13, m.count_prefix(""));
+    assert_eq!(13, m.count_prefix("B"));
+    assert_eq!(13, m.count_prefix("BY"));
+    assert_eq!(3, m.count_prefix("BYP"));
+    assert_eq!(2, m.count_prefix("BYPA"));
+    assert_eq!(0, m.count_prefix("BYX"));
+    assert_eq!(0, m.count_prefix("A"));
 }
 
 #[test]
-fn format() {
-    let mut m = TSTMap::<i64>::new();
+fn nth_rank() {
+    let m = prepare_data();
 
-    m.insert("abc", 2);
-    m.insert("abd", 1);
-    m.insert("abdd", 4);
-    m.insert("abcdefghjkik", -169_874);
+    assert_eq!(Some(("BYE".to_string(), &2)), m.nth(1));
+    assert_eq!(Some(("BYWORD".to_string(), &13)), m.nth(12));
+    assert_eq!(None, m.nth(13));
 
-    let m_str = format!("{:?}", m);
-    assert_eq!(
-        "{\"abc\": 2, \"abcdefghjkik\": -169874, \"abd\": 1, \"abdd\": 4}",
-        m_str
-    );
+    assert_eq!(0, m.rank("A"));
+    assert_eq!(0, m.rank("B"));
+    assert_eq!(1, m.rank("BYA"));
+    assert_eq!(5, m.rank("BYP"));
+    assert_eq!(13, m.rank("C"));
+    assert_eq!(13, m.rank("BYZ"));
+
+    assert_ordinals(&m);
+    assert_eq!(None, TSTMap::<i32>::new().nth(0));
 }
 
 #[test]
-fn iterator() {
-    let mut m = TSTMap::new();
-
-    m.insert("b", 2);
-    m.insert("a", 1);
-    m.insert("c", 4);
-    m.insert("aa", 13);
+fn ordinals_after_updates() {
+    let mut m = prepare_data();
 
-    let mut m_str = String::new();
-    for x in m.iter() {
-        m_str.push_str(&format!("{:?}", x));
+    m.remove("BYPASS");
+    *m.entry("BYPASSER").or_insert(0) += 1;
+    *m.entry("BYTE").or_insert(0) += 1;
+    if let Occupied(entry) = m.entry("BYLAW") {
+        entry.remove();
     }
-    assert_eq!("(\"a\", 1)(\"aa\", 13)(\"b\", 2)(\"c\", 4)", m_str);
+    m.remove_if("BYE", |_| true);
+    m.clear_prefix("BYW");
+    m.insert("A", 0);
+    m.merge_with(tstmap! { "BYTES" => 14, "A" => 1 }, |a, b| *a += b);
+    assert_ordinals(&m);
+
+    let mut buf = vec![];
+    m.write_to(&mut buf).unwrap();
+    assert_ordinals(&TSTMap::read_from(&mut buf.as_slice()).unwrap());
+
+    let mut cloned = prepare_data();
+    cloned.clone_from(&m);
+    assert_ordinals(&cloned);
+
+    m.optimize_layout();
+    assert_ordinals(&m);
 }
 
 #[test]
-fn iterator_mut() {
+fn longest_prefix_empty() {
     let mut m = TSTMap::new();
+    m.insert("abc", 1);
 
-    m.insert("b", 2);
-    m.insert("a", 1);
-    m.insert("c", 4);
-    m.insert("aa", 13);
+    assert_eq!("", m.longest_prefix("qwer"));
+    assert_eq!("", m.longest_prefix(""));
+}
+
+#[test]
+fn longest_prefix_unicode() {
+    let m = tstmap! {
+        "при" => 1,
+        "привет" => 2,
+        "🦀" => 3,
+    };
+
+    assert_eq!("привет", m.longest_prefix("приветствие"));
+    assert_eq!("при", m.longest_prefix("прибой"));
+    assert_eq!("🦀", m.longest_prefix("🦀🦀"));
+    assert_eq!("", m.longest_prefix("пр"));
+}
+
+#[test]
+fn longest_prefix_len() {
+    let m = prepare_data();
+
+    assert_eq!(6, m.longest_prefix_len("BYPASSING".chars()));
+    assert_eq!(3, m.longest_prefix_len("BYEBYE".chars()));
+    let query: Vec<char> = "BYTES".chars().collect();
+    assert_eq!(4, m.longest_prefix_len(query.iter().copied()));
+    assert_eq!(0, m.longest_prefix_len("B".chars()));
+    assert_eq!(0, m.longest_prefix_len("".chars()));
+    assert_eq!(0, TSTMap::<i32>::new().longest_prefix_len("BY".chars()));
+}
+
+#[test]
+fn divergence_point() {
+    let m = prepare_data();
+
+    assert_eq!(4, m.divergence_point("BYPASS", "BYPATH"));
+    assert_eq!(4, m.divergence_point("BYPAX", "BYPAY"));
+    assert_eq!(2, m.divergence_point("BYXA", "BYXB"));
+    assert_eq!(2, m.divergence_point("BY", "BYE"));
+    assert_eq!(0, m.divergence_point("BYE", "XBYE"));
+    assert_eq!(0, m.divergence_point("", ""));
+    assert_eq!(0, TSTMap::<i32>::new().divergence_point("BY", "BY"));
+
+    assert_eq!(2, m.keys_between_prefix("BYPASS", "BYPATH"));
+    assert_eq!(3, m.keys_between_prefix("BYPASS", "BYPRODUCT"));
+    assert_eq!(13, m.keys_between_prefix("BYE", "BYTE"));
+    assert_eq!(1, m.keys_between_prefix("BYTE", "BYTES"));
+    assert_eq!(0, m.keys_between_prefix("BYX", "BYXZ"));
+    assert_eq!(13, m.keys_between_prefix("BYE", "XBYE"));
+
+    // nodes of removed keys are no more a part of any path
+    let mut m = prepare_data();
+    m.remove("BYPASS");
+    assert_eq!(4, m.divergence_point("BYPASS", "BYPASX"));
+    m.remove("BYPATH");
+    assert_eq!(3, m.divergence_point("BYPASS", "BYPATH"));
+    assert_eq!(1, m.keys_between_prefix("BYPASS", "BYPRODUCT"));
+}
+
+#[test]
+fn longest_prefix() {
+    let mut m = TSTMap::new();
+    m.insert("abc", 1);
+    m.insert("abcd", 1);
+    m.insert("abce", 1);
+    m.insert("abca", 1);
+    m.insert("zxd", 1);
+    m.insert("add", 1);
+    m.insert("abcdef", 1);
+
+    assert_eq!("abcd", m.longest_prefix("abcde"));
+    assert_eq!("abcdef", m.longest_prefix("abcdef"));
+}
+
+#[test]
+fn matching_prefixes_longest_first() {
+    let mut m = prepare_data();
+    m.insert("B", 14);
+    m.insert("ДОМ", 15);
+    m.insert("ДОМА", 16);
+
+    let routes = m
+        .matching_prefixes_longest_first("BYEBYE")
+        .collect::<Vec<_>>();
+    assert_eq!(vec![("BYE", &2), ("BY", &1), ("B", &14)], routes);
+    assert_eq!(
+        vec![("B", &14), ("BY", &1)],
+        m.matching_prefixes_longest_first("BYG")
+            .rev()
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![("ДОМА", &16), ("ДОМ", &15)],
+        m.matching_prefixes_longest_first("ДОМАШНИЙ")
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        m.longest_prefix("BYTES"),
+        m.matching_prefixes_longest_first("BYTES").next().unwrap().0
+    );
+    assert_eq!(3, m.matching_prefixes_longest_first("BYTE").len());
+    assert_eq!(0, m.matching_prefixes_longest_first("").count());
+    assert_eq!(0, m.matching_prefixes_longest_first("XBYE").count());
+}
+
+#[test]
+fn longest_suffix() {
+    let mut m = TSTMap::new();
+    m.insert_reversed("uk", 1);
+    m.insert_reversed("co.uk", 2);
+    m.insert_reversed("рф", 3);
+    m.insert_reversed("москва.рф", 4);
+
+    assert_eq!("co.uk", m.longest_suffix("bbc.co.uk"));
+    assert_eq!("co.uk", m.longest_suffix("co.uk"));
+    assert_eq!("uk", m.longest_suffix("o.uk"));
+    assert_eq!("москва.рф", m.longest_suffix("кремль.москва.рф"));
+    assert_eq!("рф", m.longest_suffix("сайт.рф"));
+    assert_eq!("", m.longest_suffix("example.com"));
+    assert_eq!("", m.longest_suffix(""));
+    assert_eq!(Some(1), m.insert_reversed("uk", 10));
+    assert_eq!(4, m.len());
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn longest_suffix_with_normalization() {
+    use self::tst::Normalization;
+
+    // combining marks stay after their letters, as keys are normalized before reversing
+    let mut m = TSTMap::with_normalization(Normalization::Nfd);
+    m.insert_reversed("caf\u{e9}", 1);
+    assert_eq!(vec!["\u{301}efac"], m.keys().collect::<Vec<_>>());
+    assert_eq!("caf\u{e9}", m.longest_suffix("le caf\u{e9}"));
+    assert_eq!("cafe\u{301}", m.longest_suffix("le cafe\u{301}"));
+    assert_eq!("", m.longest_suffix("le cafe"));
+}
+
+#[test]
+fn new_reversed() {
+    let mut m = TSTMap::new_reversed();
+    m.insert("uk", 1);
+    m.insert("co.uk", 2);
+    m.insert("gov.uk", 3);
+    m.insert("com", 4);
+    assert_eq!(Some(4), m.insert_reversed("com", 5));
+
+    assert_eq!(Some(&2), m.get("co.uk"));
+    assert_eq!(None, m.get("ku.oc"));
+    assert!(m.contains_key("gov.uk"));
+    assert_eq!("co.uk", m.longest_suffix("bbc.co.uk"));
+    assert_eq!("uk", m.longest_suffix("bbc.uk"));
+    assert_eq!("co.uk", m.longest_prefix("bbc.co.uk"));
+    assert_eq!(5, m.longest_prefix_len("bbc.co.uk".chars()));
+    assert_eq!(
+        vec![("co.uk", &2), ("uk", &1)],
+        m.matching_prefixes_longest_first("bbc.co.uk")
+            .collect::<Vec<_>>()
+    );
+
+    // keys come back as given, in the order of their reversed chars
+    let keys = vec!["uk", "co.uk", "gov.uk", "com"];
+    assert_eq!(keys, m.keys().collect::<Vec<_>>());
+    assert_eq!(Some(("com".to_string(), &5)), m.iter().next_back());
+    assert_eq!(
+        keys,
+        m.clone().into_iter().map(|(k, _)| k).collect::<Vec<_>>()
+    );
+    assert_eq!(Some(("com".to_string(), &5)), m.peek_max());
+    assert_eq!(Some(("co.uk".to_string(), &2)), m.nth(1));
+    let mut visited = vec![];
+    m.for_each_ref(|key, _| visited.push(key.to_string()));
+    assert_eq!(keys, visited);
+
+    // starts of keys are their ends
+    assert_eq!(3, m.count_prefix("uk"));
+    assert_eq!(
+        vec!["co.uk", "gov.uk"],
+        m.prefix_keys(".uk").collect::<Vec<_>>()
+    );
+    let mut completions: Vec<_> = m.completions("uk").map(|(k, _)| k).collect();
+    completions.sort();
+    assert_eq!(vec!["", "co.", "gov."], completions);
+    assert_eq!(
+        vec!["co.uk"],
+        m.wildcard_iter("c[aeo].uk")
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["co.uk", "com"],
+        m.matching("co*").keys().collect::<Vec<_>>()
+    );
+    assert_eq!(vec!["com"], m.keys_of_length(3).collect::<Vec<_>>());
+
+    match m.entry("co.uk") {
+        Occupied(entry) => assert_eq!(("co.uk".to_string(), 2), entry.remove_entry()),
+        Vacant(_) => panic!("co.uk is missing"),
+    }
+    assert_eq!("co.uk", m.entry("co.uk").key());
+    *m.entry("co.uk").or_insert(0) += 20;
+    assert_eq!(Some(&20), m.get("co.uk"));
+    assert_eq!(Some(("gov.uk".to_string(), 3)), m.remove_entry("gov.uk"));
+
+    let mut cursor = m.cursor(".uk");
+    cursor.insert_suffix("org", 6);
+    assert_eq!(Some(&20), cursor.get_suffix("co"));
+    assert_eq!(Some(&6), m.get("org.uk"));
+
+    for (key, value) in m.iter_mut() {
+        if key.ends_with(".uk") {
+            *value += 100;
+        }
+    }
+    let drained: Vec<_> = m.drain_filter(|key, _| key.starts_with("co")).collect();
+    assert_eq!(
+        vec![("co.uk".to_string(), 120), ("com".to_string(), 5)],
+        drained
+    );
+    assert_eq!(
+        vec![("uk".to_string(), &1), ("org.uk".to_string(), &106)],
+        m.to_sorted_vec()
+    );
+    assert_eq!(Ok(()), m.validate());
+
+    // written with keys as given
+    let mut blob = vec![];
+    m.write_to(&mut blob).unwrap();
+    let loaded: TSTMap<i32> = TSTMap::read_from(&mut &blob[..]).unwrap();
+    assert_eq!(Some(&106), loaded.get("org.uk"));
+
+    let mut other = TSTMap::new_reversed();
+    other.insert("ac.uk", 7);
+    other.insert("uk", 10);
+    let merged = merge_sorted(vec![m, other], |existing, incoming| *existing += incoming);
+    assert_eq!(
+        vec![
+            ("uk".to_string(), &11),
+            ("ac.uk".to_string(), &7),
+            ("org.uk".to_string(), &106)
+        ],
+        merged.to_sorted_vec()
+    );
+}
+
+#[test]
+fn common_prefix() {
+    let m = prepare_data();
+    assert_eq!("BY", m.common_prefix());
+
+    let m = tstmap! {
+        "BYPASS" => 6,
+        "BYPATH" => 7,
+    };
+    assert_eq!("BYPA", m.common_prefix());
+}
+
+#[test]
+fn common_prefix_single_key() {
+    let m = tstmap! {
+        "BYPASS" => 6,
+    };
+    assert_eq!("BYPASS", m.common_prefix());
+}
+
+#[test]
+fn common_prefix_empty() {
+    let m = TSTMap::<i32>::new();
+    assert_eq!("", m.common_prefix());
+
+    let m = tstmap! {
+        "b" => 1,
+        "a" => 2,
+    };
+    assert_eq!("", m.common_prefix());
+}
+
+#[test]
+fn common_prefix_after_removals() {
+    let mut m = tstmap! {
+        "a" => 1,
+        "bc" => 2,
+        "bd" => 3,
+    };
+    m.remove("a");
+    assert_eq!("b", m.common_prefix());
+    m.soft_remove("bc");
+    assert_eq!("bd", m.common_prefix());
+
+    let mut m = tstmap! {
+        "bc" => 2,
+        "bd" => 3,
+        "c" => 4,
+    };
+    m.soft_remove("c");
+    assert_eq!("b", m.common_prefix());
+    m.remove("bc");
+    m.remove("bd");
+    assert_eq!("", m.common_prefix());
+}
+
+#[test]
+fn access_by_index() {
+    let mut m = TSTMap::new();
+
+    m.insert("abc", 2);
+    m.insert("abd", 1);
+    m.insert("abdd", 4);
+
+    assert_eq!(2, m["abc"]);
+    assert_eq!(1, m["abd"]);
+    assert_eq!(4, m["abdd"]);
+}
+
+#[test]
+fn access_by_index_mut() {
+    let mut m = TSTMap::new();
+
+    m.insert("abc", 2);
+    m.insert("abd", 1);
+    m.insert("abdd", 4);
+    {
+        let v = &mut m["abc"];
+        *v += 1;
+    }
+
+    assert_eq!(3, m["abc"]);
+    assert_eq!(1, m["abd"]);
+    assert_eq!(4, m["abdd"]);
+}
+
+#[test]
+fn access_by_string_index() {
+    let mut m = prepare_data();
+    let key = "BYE".to_string();
+
+    assert_eq!(2, m[&key]);
+    m[&key] += 10;
+    assert_eq!(12, m[key.clone()]);
+    m[key.clone()] += 10;
+    assert_eq!(22, m[key]);
+}
+
+#[test]
+#[should_panic]
+fn access_by_wrong_string_index() {
+    let m = prepare_data();
+
+    assert_eq!(3, m["BYX".to_string()]);
+}
+
+#[test]
+#[should_panic]
+fn access_by_wrong_index() {
+    let mut m = TSTMap::new();
+
+    m.insert("abc", 2);
+    m.insert("abd", 1);
+    m.insert("abdd", 4);
+
+    assert_eq!(3, m["a"]);
+}
+
+#[test]
+fn format_empty() {
+    let m = TSTMap::<u64>::new();
+
+    assert_eq!("{}", format!("{:?}", m));
+}
+
+#[test]
+fn format() {
+    let mut m = TSTMap::<i64>::new();
+
+    m.insert("abc", 2);
+    m.insert("abd", 1);
+    m.insert("abdd", 4);
+    m.insert("abcdefghjkik", -169_874);
+
+    let m_str = format!("{:?}", m);
+    assert_eq!(
+        "{\"abc\": 2, \"abcdefghjkik\": -169874, \"abd\": 1, \"abdd\": 4}",
+        m_str
+    );
+}
+
+#[test]
+fn format_alternate() {
+    let mut m = TSTMap::<i64>::new();
+
+    m.insert("abd", 1);
+    m.insert("abc", 2);
+
+    assert_eq!(
+        "{\n    \"abc\": 2,\n    \"abd\": 1,\n}",
+        format!("{:#?}", m)
+    );
+    assert_eq!("{}", format!("{:#?}", TSTMap::<i64>::new()));
+}
+
+#[test]
+fn iterator() {
+    let mut m = TSTMap::new();
+
+    m.insert("b", 2);
+    m.insert("a", 1);
+    m.insert("c", 4);
+    m.insert("aa", 13);
+
+    let mut m_str = String::new();
+    for x in m.iter() {
+        m_str.push_str(&format!("{:?}", x));
+    }
+    assert_eq!("(\"a\", 1)(\"aa\", 13)(\"b\", 2)(\"c\", 4)", m_str);
+}
+
+#[test]
+fn iter_from() {
+    let m = prepare_data();
+
+    let keys = |start: &str| m.iter_from(start).map(|(k, _)| k).collect::<Vec<_>>();
+    assert_eq!(vec!["BYWAY", "BYWORD"], keys("BYW"));
+    assert_eq!(vec!["BYWAY", "BYWORD"], keys("BYV"));
+    assert_eq!(vec!["BYWORD"], keys("BYWAZ"));
+    assert_eq!(vec!["BYWORD"], keys("BYWORD"));
+    assert!(keys("BYWORDS").is_empty());
+    assert!(keys("C").is_empty());
+    assert_eq!(13, keys("").len());
+    assert_eq!(13, keys("A").len());
+    assert_eq!(13, keys("BY").len());
+    assert_eq!(12, keys("BYA").len());
+
+    for start in [
+        "B", "BYE", "BYF", "BYLA", "BYLAWS", "BYP", "BYPATH", "BYQ", "BYTE", "BYZ",
+    ] {
+        let expected: Vec<_> = m.iter().filter(|(k, _)| k.as_str() >= start).collect();
+        let iter = m.iter_from(start);
+        assert_eq!(expected.len(), iter.len());
+        assert_eq!(expected, iter.collect::<Vec<_>>());
+        let rev: Vec<_> = m.iter_from(start).rev().collect();
+        assert_eq!(expected.into_iter().rev().collect::<Vec<_>>(), rev);
+    }
+    assert_eq!(0, TSTMap::<i32>::new().iter_from("a").count());
+}
+
+#[test]
+fn iter_advance_to() {
+    let m = prepare_data();
+    let starts = [
+        "", "A", "B", "BY", "BYE", "BYF", "BYLA", "BYLAWS", "BYP", "BYPATH", "BYQ", "BYTE", "BYZ",
+    ];
+
+    for start in starts {
+        let expected: Vec<_> = m.iter().filter(|(k, _)| k.as_str() >= start).collect();
+        let mut iter = m.iter();
+        iter.advance_to(start);
+        assert_eq!(expected.len(), iter.len());
+        assert_eq!(expected, iter.collect::<Vec<_>>());
+
+        let mut iter = m.prefix_iter("BYP");
+        iter.advance_to(start);
+        let expected: Vec<_> = m
+            .prefix_iter("BYP")
+            .filter(|(k, _)| k.as_str() >= start)
+            .collect();
+        assert_eq!(expected, iter.collect::<Vec<_>>());
+    }
+
+    // pages of 3 entries resumed after the last key of the previous page
+    let mut iter = m.iter();
+    let mut pages = vec![];
+    let mut page: Vec<_> = iter.by_ref().take(3).map(|(k, _)| k).collect();
+    while let Some(last) = page.last().cloned() {
+        pages.push(page);
+        let mut resumed = m.iter();
+        resumed.advance_to(&format!("{}\0", last));
+        page = resumed.take(3).map(|(k, _)| k).collect();
+    }
+    assert_eq!(5, pages.len());
+    assert_eq!(m.keys().collect::<Vec<_>>(), pages.concat());
+
+    // going back is a no-op
+    let mut iter = m.iter();
+    iter.advance_to("BYT");
+    iter.advance_to("BYE");
+    assert_eq!(Some(("BYTE".to_string(), &11)), iter.next());
+
+    // the end which was yielded from the back is not passed
+    let mut iter = m.iter();
+    assert_eq!(Some(("BYWORD".to_string(), &13)), iter.next_back());
+    assert_eq!(Some(("BYWAY".to_string(), &12)), iter.next_back());
+    iter.advance_to("BYTE");
+    assert_eq!(1, iter.len());
+    assert_eq!(Some(("BYTE".to_string(), &11)), iter.next());
+    assert_eq!(None, iter.next_back());
+    let mut iter = m.iter();
+    iter.next_back();
+    iter.advance_to("BYWORD");
+    assert_eq!(None, iter.next());
+}
+
+#[test]
+fn iterator_mut() {
+    let mut m = TSTMap::new();
+
+    m.insert("b", 2);
+    m.insert("a", 1);
+    m.insert("c", 4);
+    m.insert("aa", 13);
+
+    for (_, v) in m.iter_mut() {
+        *v *= 3;
+    }
+    assert_eq!(Some(&6), m.get("b"));
+    assert_eq!(Some(&3), m.get("a"));
+    assert_eq!(Some(&12), m.get("c"));
+    assert_eq!(Some(&39), m.get("aa"));
+}
+
+#[test]
+fn suffix_wildcard_iter() {
+    let m = tstmap! {
+        "ring" => 1,
+        "sing" => 2,
+        "string" => 3,
+        "ing" => 4,
+        "ingot" => 5,
+        "бегущий" => 6,
+        "поющий" => 7,
+    };
+
+    let keys = |pat| {
+        m.suffix_wildcard_iter(pat)
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["ring", "sing", "string"], keys(".ing"));
+    assert_eq!(vec!["string"], keys("..ing"));
+    assert_eq!(vec!["ring", "string"], keys("[rt]ing"));
+    assert_eq!(vec!["бегущий", "поющий"], keys("щий"));
+    assert_eq!(vec!["поющий"], keys("[^у]щий"));
+    assert_eq!(m.len(), keys("").len());
+    assert!(keys("..........").is_empty());
+}
+
+#[test]
+fn keys_of_length() {
+    let m = prepare_data();
+
+    assert_eq!(
+        vec!["BYLAW", "BYWAY"],
+        m.keys_of_length(5).collect::<Vec<_>>()
+    );
+    assert_eq!(vec!["BY"], m.keys_of_length(2).collect::<Vec<_>>());
+    assert_eq!(
+        vec!["BYPRODUCT", "BYSTANDER"],
+        m.keys_of_length(9).collect::<Vec<_>>()
+    );
+    assert_eq!(0, m.keys_of_length(0).count());
+    assert_eq!(0, m.keys_of_length(1).count());
+    assert_eq!(0, m.keys_of_length(10).count());
+    assert_eq!(0, m.keys_of_length(usize::MAX).count());
+    assert_eq!(0, TSTMap::<i32>::new().keys_of_length(usize::MAX).count());
+}
+
+#[test]
+fn keys_of_length_unicode() {
+    let m = tstmap! {
+        "ab" => 1,
+        "аб" => 2,
+        "абв" => 3,
+        "🦀🦀" => 4,
+    };
+
+    assert_eq!(
+        vec!["ab", "аб", "🦀🦀"],
+        m.keys_of_length(2).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn fuzzy_prefix_iter() {
+    let m = prepare_data();
+
+    let keys = |pref, dist| {
+        m.fuzzy_prefix_iter(pref, dist)
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["BYPASS", "BYPATH", "BYPRODUCT"], keys("BYP", 0));
+    assert_eq!(vec!["BYPASS", "BYPATH"], keys("BYPAH", 1));
+    assert_eq!(vec!["BYWORD"], keys("BYWRD", 1));
+    assert_eq!(
+        vec!["BYPRODUCT", "BYROAD", "BYWAY", "BYWORD"],
+        keys("BYWRD", 2)
+    );
+    assert_eq!(13, m.fuzzy_prefix_iter("XX", 2).count());
+    assert_eq!(13, m.fuzzy_prefix_iter("", 0).count());
+    assert_eq!(0, m.fuzzy_prefix_iter("XYZ", 1).count());
+    assert_eq!(
+        vec![("BYE".to_string(), &2), ("BYTE".to_string(), &11)],
+        m.fuzzy_prefix_iter("BTE", 1).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn fuzzy_prefix_iter_no_duplicates() {
+    let m = tstmap! {
+        "ab" => 1,
+        "abc" => 2,
+        "abcd" => 3,
+        "b" => 4,
+    };
+
+    assert_eq!(
+        vec![
+            ("ab".to_string(), &1),
+            ("abc".to_string(), &2),
+            ("abcd".to_string(), &3)
+        ],
+        m.fuzzy_prefix_iter("abc", 1).collect::<Vec<_>>()
+    );
+    assert_eq!(4, m.fuzzy_prefix_iter("abc", 3).count());
+}
+
+fn osa_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in 0..=a.len() {
+        for j in 0..=b.len() {
+            d[i][j] = if i == 0 || j == 0 {
+                i + j
+            } else {
+                let cost = usize::from(a[i - 1] != b[j - 1]);
+                let mut v = (d[i - 1][j] + 1)
+                    .min(d[i][j - 1] + 1)
+                    .min(d[i - 1][j - 1] + cost);
+                if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                    v = v.min(d[i - 2][j - 2] + 1);
+                }
+                v
+            };
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[test]
+fn damerau_iter() {
+    let m = prepare_data();
+
+    let keys = |query, dist| {
+        m.damerau_iter(query, dist)
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["BYE", "BYTE"], keys("BYET", 1));
+    assert_eq!(vec!["BYLAW"], keys("BYLWA", 1));
+    assert_eq!(vec!["BY", "BYE", "BYTE"], keys("YBE", 2));
+    assert_eq!(
+        vec![("BYGONE".to_string(), &3)],
+        m.damerau_iter("BYGONE", 0).collect::<Vec<_>>()
+    );
+    assert_eq!(0, m.damerau_iter("BYG", 0).count());
+    assert_eq!(0, m.damerau_iter("", 1).count());
+    assert_eq!(13, m.damerau_iter("", 9).count());
+
+    for query in ["BYWYA", "YB", "BYPTAH", "BYTSANDER", "", "XYZ"] {
+        for dist in 0..4 {
+            let expected = m
+                .iter()
+                .filter(|(k, _)| osa_distance(k, query) <= dist)
+                .collect::<Vec<_>>();
+            assert_eq!(expected, m.damerau_iter(query, dist).collect::<Vec<_>>());
+        }
+    }
+    for key in m.keys() {
+        assert_eq!(
+            vec![(key.clone(), m.get(&key).unwrap())],
+            m.damerau_iter(&key, 0).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn write_to_read_from() {
+    let m = prepare_data();
+    let mut blob = vec![];
+    m.write_to(&mut blob).unwrap();
+
+    let loaded = TSTMap::<i32>::read_from(&mut &blob[..]).unwrap();
+    assert_eq!(m, loaded);
+    assert_eq!(13, loaded.len());
+    assert_eq!(Some(&8), loaded.get("BYPRODUCT"));
+}
+
+#[test]
+fn write_to_read_from_empty() {
+    let mut blob = vec![];
+    TSTMap::<i32>::new().write_to(&mut blob).unwrap();
+    let loaded = TSTMap::<i32>::read_from(&mut &blob[..]).unwrap();
+    assert!(loaded.is_empty());
+
+    // nodes without values are kept as well
+    let mut m = TSTMap::<i32>::new();
+    m.insert("abc", 1);
+    m.remove("abc");
+    m.entry("abd");
+    blob.clear();
+    m.write_to(&mut blob).unwrap();
+    let mut loaded = TSTMap::<i32>::read_from(&mut &blob[..]).unwrap();
+    assert!(loaded.is_empty());
+    loaded.insert("ab", 1);
+    assert_eq!(1, loaded.len());
+}
+
+#[derive(Debug, PartialEq)]
+struct Point {
+    x: i16,
+    y: i16,
+}
+
+// packs a point into 3 bytes, points off the 12-bit grid are not supported
+struct PackedPoint;
+
+impl ValueCodec<Point> for PackedPoint {
+    fn encode<W: Write>(&self, value: &Point, w: &mut W) -> io::Result<()> {
+        let packed = ((value.x as u32) << 12) | value.y as u32;
+        w.write_all(&packed.to_le_bytes()[..3])
+    }
+    fn decode<R: Read>(&self, r: &mut R) -> io::Result<Point> {
+        let mut buf = [0; 4];
+        r.read_exact(&mut buf[..3])?;
+        let packed = u32::from_le_bytes(buf);
+        if packed == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "zero point"));
+        }
+        Ok(Point {
+            x: (packed >> 12) as i16,
+            y: (packed & 0xfff) as i16,
+        })
+    }
+}
+
+#[test]
+fn save_load_with_codec() {
+    let m = tstmap! {
+        "a" => Point { x: 1, y: 2 },
+        "ab" => Point { x: 4095, y: 4095 },
+        "слово" => Point { x: 7, y: 0 },
+    };
+    let mut blob = vec![];
+    m.save(&mut blob, &PackedPoint).unwrap();
+
+    let loaded = TSTMap::load(&mut &blob[..], &PackedPoint).unwrap();
+    assert_eq!(m, loaded);
+    assert_eq!(Some(&Point { x: 4095, y: 4095 }), loaded.get("ab"));
+
+    // errors of the codec are passed through
+    let mut m = TSTMap::new();
+    m.insert("zero", Point { x: 0, y: 0 });
+    blob.clear();
+    m.save(&mut blob, &PackedPoint).unwrap();
+    let err = TSTMap::load(&mut &blob[..], &PackedPoint).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    assert_eq!("zero point", err.to_string());
+}
+
+#[test]
+fn write_to_read_from_strings() {
+    let m = tstmap! {
+        "слово" => "word".to_string(),
+        "🦀" => "".to_string(),
+        "a" => "буква".to_string(),
+    };
+    let mut blob = vec![];
+    m.write_to(&mut blob).unwrap();
+
+    let loaded = TSTMap::<String>::read_from(&mut &blob[..]).unwrap();
+    assert_eq!(m, loaded);
+}
+
+#[test]
+fn read_from_invalid() {
+    use std::io::ErrorKind;
+
+    let mut blob = vec![];
+    prepare_data().write_to(&mut blob).unwrap();
+
+    let err = |data: &[u8]| TSTMap::<i32>::read_from(&mut &data[..]).unwrap_err().kind();
+    assert_eq!(ErrorKind::InvalidData, err(b"TSX\0"));
+    assert_eq!(ErrorKind::UnexpectedEof, err(&blob[..blob.len() - 1]));
+    assert_eq!(ErrorKind::UnexpectedEof, err(b""));
+
+    let mut version = blob.clone();
+    version[4] = 2;
+    assert_eq!(ErrorKind::InvalidData, err(&version));
+
+    let mut size = blob.clone();
+    size[5] = 12;
+    assert_eq!(ErrorKind::InvalidData, err(&size));
+
+    // "b" with "a" in its lt child, swapped to "a" with "b" in its lt child
+    let m: TSTMap<u8> = tstmap! { "b" => 1, "a" => 2 };
+    let mut blob = vec![];
+    m.write_to(&mut blob).unwrap();
+    for byte in blob[14..].iter_mut() {
+        *byte = match *byte {
+            b'a' => b'b',
+            b'b' => b'a',
+            other => other,
+        };
+    }
+    let e = TSTMap::<u8>::read_from(&mut &blob[..]).unwrap_err();
+    assert_eq!(ErrorKind::InvalidData, e.kind());
+}
+
+#[test]
+fn write_to_read_from_sizes() {
+    // sizes are written in 64 bits on every platform
+    let sizes: TSTMap<usize> = tstmap! { "a" => 1, "b" => usize::MAX };
+    let wide: TSTMap<u64> = tstmap! { "a" => 1, "b" => usize::MAX as u64 };
+    let (mut blob, mut wide_blob) = (vec![], vec![]);
+    sizes.write_to(&mut blob).unwrap();
+    wide.write_to(&mut wide_blob).unwrap();
+    assert_eq!(wide_blob, blob);
+    assert_eq!(sizes, TSTMap::read_from(&mut &blob[..]).unwrap());
+
+    let offsets: TSTMap<isize> = tstmap! { "a" => -1, "b" => isize::MIN };
+    blob.clear();
+    offsets.write_to(&mut blob).unwrap();
+    assert_eq!(offsets, TSTMap::read_from(&mut &blob[..]).unwrap());
+}
+
+#[test]
+fn from_reader() {
+    let data = "BYE\t2\r\nBY\t1\n\n\r\nBYTE\t10\nBYTE\t11";
+    let m = TSTMap::from_reader(data.as_bytes(), |v| v.parse::<i32>()).unwrap();
+
+    assert_eq!(tstmap! { "BY" => 1, "BYE" => 2, "BYTE" => 11 }, m);
+}
+
+#[test]
+fn from_reader_values_with_tabs() {
+    let data = "key\ta\tb\n";
+    let m =
+        TSTMap::from_reader(data.as_bytes(), |v| Ok::<_, std::io::Error>(v.to_string())).unwrap();
+
+    assert_eq!("a\tb", m["key"]);
+}
+
+#[test]
+fn from_reader_invalid() {
+    use std::io::ErrorKind;
+
+    let err = |data: &str| TSTMap::from_reader(data.as_bytes(), |v| v.parse::<i32>()).unwrap_err();
+    assert_eq!(ErrorKind::InvalidData, err("BY\t1\nBYE\n").kind());
+    assert_eq!(ErrorKind::InvalidData, err("\t1\n").kind());
+    let e = err("BY\t1\nBYE\ttwo\n");
+    assert_eq!(ErrorKind::InvalidData, e.kind());
+    assert!(e.to_string().starts_with("line 2: "));
+    let data = b"BY\t1\n\xff\t2";
+    let e = TSTMap::from_reader(&data[..], |v| v.parse::<i32>()).unwrap_err();
+    assert_eq!(ErrorKind::InvalidData, e.kind());
+}
+
+#[test]
+fn peek_min_max() {
+    let mut m = prepare_data();
+
+    assert_eq!(Some(("BY".to_string(), &1)), m.peek_min());
+    assert_eq!(Some(("BYWORD".to_string(), &13)), m.peek_max());
+
+    m.insert("A", 0);
+    m.insert("BYWORDS", 14);
+    assert_eq!(Some(("A".to_string(), &0)), m.peek_min());
+    assert_eq!(Some(("BYWORDS".to_string(), &14)), m.peek_max());
+    assert_eq!(15, m.len());
+}
+
+#[test]
+fn peek_min_max_skips_empty_nodes() {
+    let mut m = TSTMap::new();
+    m.entry("aaa");
+    m.entry("zzz");
+    assert_eq!(None, m.peek_min());
+    assert_eq!(None, m.peek_max());
+
+    m.insert("m", 1);
+    m.insert("mn", 2);
+    assert_eq!(Some(("m".to_string(), &1)), m.peek_min());
+    assert_eq!(Some(("mn".to_string(), &2)), m.peek_max());
+}
+
+#[test]
+fn stats() {
+    let m = prepare_data();
+    let stats = m.stats();
+
+    assert_eq!(13, stats.values);
+    assert_eq!(stats.nodes, stats.children.iter().sum::<usize>());
+    // every node except the root is a child of some other one
+    assert_eq!(
+        stats.nodes - 1,
+        stats.children[1] + 2 * stats.children[2] + 3 * stats.children[3]
+    );
+    let key_len: usize = m.keys().map(|k| k.chars().count()).sum();
+    assert_eq!(key_len as f64 / 13.0, stats.avg_key_len);
+    assert!(stats.max_depth >= 9);
+}
+
+#[test]
+fn stats_sorted_inserts() {
+    let mut m = TSTMap::new();
+    for c in "abcdef".chars() {
+        m.insert(c.to_string(), 1);
+    }
+    let stats = m.stats();
+
+    assert_eq!(6, stats.nodes);
+    assert_eq!(6, stats.max_depth);
+    assert_eq!([1, 5, 0, 0], stats.children);
+    assert_eq!(1.0, stats.avg_key_len);
+}
+
+#[test]
+fn validate() {
+    let mut m = prepare_data();
+    assert_eq!(Ok(()), m.validate());
+
+    m.remove("BYE");
+    m.soft_remove("BYLAW");
+    m.entry("BYX");
+    m.clear_prefix("BYP");
+    m.insert("A", 0);
+    m.optimize_layout();
+    assert_eq!(Ok(()), m.validate());
+
+    m.size += 1;
+    assert!(m.validate().is_err());
+    assert_eq!(Ok(()), TSTMap::<i32>::new().validate());
+}
+
+#[test]
+fn validate_broken_order() {
+    let mut m = TSTMap::new();
+    m.insert("b", 1);
+    m.insert("a", 2);
+    m.root.as_mut().as_node_ref().c = '0';
+    assert!(m.validate().unwrap_err().contains("out of bounds"));
+}
+
+#[test]
+fn stats_empty() {
+    assert_eq!(tst::map::TSTStats::default(), TSTMap::<i32>::new().stats());
+}
+
+#[test]
+fn increment() {
+    let mut m = TSTMap::<u64>::new();
+    let text = "the cat and the dog and the bird";
+    for word in text.split(' ') {
+        m.increment(word);
+    }
+
+    assert_eq!(5, m.len());
+    assert_eq!(Some(&3), m.get("the"));
+    assert_eq!(Some(&2), m.get("and"));
+    assert_eq!(Some(&1), m.get("cat"));
+    let key = "the".to_string();
+    assert_eq!(4, m.increment(&key));
+}
+
+#[test]
+fn count_prefixes_of_length() {
+    let mut m = TSTMap::<u64>::new();
+    for (path, n) in [
+        ("/api/users", 3),
+        ("/api/items", 2),
+        ("/a", 1),
+        ("/static/app.js", 5),
+        ("/статьи", 7),
+        ("/статус", 1),
+    ] {
+        m.bump_by(path, n);
+    }
+
+    assert_eq!(
+        vec![
+            ("/a".to_string(), 1),
+            ("/api".to_string(), 5),
+            ("/sta".to_string(), 5),
+            ("/ста".to_string(), 8),
+        ],
+        m.count_prefixes_of_length(4)
+    );
+    assert_eq!(vec![("".to_string(), 19)], m.count_prefixes_of_length(0));
+    assert_eq!(m.len(), m.count_prefixes_of_length(100).len());
+    assert!(TSTMap::<u64>::new().count_prefixes_of_length(1).is_empty());
+}
+
+#[test]
+fn bump_by() {
+    let mut m = prepare_data();
+
+    assert_eq!(11, m.bump_by("BY", 10));
+    assert_eq!(-5, m.bump_by("BYX", -5));
+    assert_eq!(14, m.len());
+    assert_eq!(Some(&11), m.get("BY"));
+}
+
+#[test]
+fn optimize_layout() {
+    let mut m = prepare_data();
+    let orig = m.clone();
+    m.entry("BYX");
+    m.insert("BYZ", 100);
+    m.remove("BYZ");
+
+    m.optimize_layout();
+    assert_eq!(orig, m);
+    assert_eq!(13, m.len());
+
+    m.insert("BYZ", 100);
+    assert_eq!(Some(100), m.remove("BYZ"));
+    assert_eq!(Some(1), m.remove("BY"));
+    assert_eq!(12, m.len());
+
+    let mut empty = TSTMap::<i32>::new();
+    empty.optimize_layout();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn canonicalize() {
+    let m = prepare_data();
+    let mut reversed = TSTMap::new();
+    for (key, value) in m.iter().collect::<Vec<_>>().into_iter().rev() {
+        reversed.insert(key, *value);
+    }
+    let (mut bytes, mut reversed_bytes) = (vec![], vec![]);
+    m.write_to(&mut bytes).unwrap();
+    reversed.write_to(&mut reversed_bytes).unwrap();
+    assert_ne!(bytes, reversed_bytes);
+
+    let mut m = m;
+    m.canonicalize();
+    reversed.canonicalize();
+    let (mut bytes, mut reversed_bytes) = (vec![], vec![]);
+    m.write_to(&mut bytes).unwrap();
+    reversed.write_to(&mut reversed_bytes).unwrap();
+    assert_eq!(bytes, reversed_bytes);
+
+    assert_eq!(prepare_data(), m);
+    assert_eq!(13, m.len());
+    assert_eq!(Some(("BYTE".to_string(), &11)), m.nth(10));
+    assert_eq!(m.stats(), reversed.stats());
+}
+
+#[test]
+fn optimize_layout_drops_values_once() {
+    use std::rc::Rc;
+
+    let value = Rc::new(1);
+    let mut m = TSTMap::new();
+    for key in ["a", "ab", "b", "ba"] {
+        m.insert(key, value.clone());
+    }
+    m.optimize_layout();
+    assert_eq!(5, Rc::strong_count(&value));
+    m.remove("ab");
+    assert_eq!(4, Rc::strong_count(&value));
+}
+
+#[test]
+fn alphabet() {
+    let m = prepare_data();
+
+    assert_eq!(
+        "ABCDEGHILNOPRSTUWY".chars().collect::<Vec<_>>(),
+        m.alphabet()
+    );
+    assert!(m.uses_only(&"ABCDEGHILNOPRSTUWY".chars().collect::<Vec<_>>()));
+    assert!(!m.uses_only(&"ABCDEGHILNOPRSTUW".chars().collect::<Vec<_>>()));
+    assert!(TSTMap::<i32>::new().alphabet().is_empty());
+    assert!(TSTMap::<i32>::new().uses_only(&[]));
+}
+
+#[test]
+fn alphabet_skips_nodes_without_values() {
+    let mut m = tstmap! { "ab" => 1, "ба" => 2 };
+    m.entry("xyz");
+    m.entry("abc");
+
+    assert_eq!(vec!['a', 'b', 'а', 'б'], m.alphabet());
+}
+
+#[test]
+fn contains_value() {
+    let m = prepare_data();
+
+    assert!(m.contains_value(&1));
+    assert!(m.contains_value(&13));
+    assert!(!m.contains_value(&14));
+    assert!(!TSTMap::new().contains_value(&1));
+}
+
+#[test]
+fn find_key() {
+    let mut m = prepare_data();
+    m.insert("BYWAYS", 4);
+
+    assert_eq!(Some("BYSTANDER".to_string()), m.find_key(&10));
+    assert_eq!(Some("BYLAW".to_string()), m.find_key(&4));
+    assert_eq!(None, m.find_key(&0));
+
+    m.remove("BYLAW");
+    assert_eq!(Some("BYWAYS".to_string()), m.find_key(&4));
+}
+
+#[test]
+fn suggest() {
+    let m = prepare_data();
+
+    assert_eq!(
+        vec![("BYTE".to_string(), 0), ("BYE".to_string(), 1)],
+        m.suggest("BYTE", 1, 10)
+    );
+    assert_eq!(
+        vec![("BYPASS".to_string(), 1), ("BYPATH".to_string(), 1)],
+        m.suggest("BYPATS", 1, 10)
+    );
+    assert_eq!(vec![("BYPASS".to_string(), 1)], m.suggest("BYPATS", 1, 1));
+    assert_eq!(
+        vec![
+            ("BY".to_string(), 1),
+            ("BYE".to_string(), 2),
+            ("BYTE".to_string(), 3)
+        ],
+        m.suggest("B", 3, 3)
+    );
+    assert!(m.suggest("XYZ", 1, 10).is_empty());
+    assert!(m.suggest("BYTE", 1, 0).is_empty());
+    assert!(TSTMap::<i32>::new().suggest("BYTE", 3, 10).is_empty());
+}
+
+#[test]
+fn suggest_unicode() {
+    let m = tstmap! {
+        "привет" => 1,
+        "привед" => 2,
+        "превед" => 3,
+    };
+
+    assert_eq!(
+        vec![
+            ("привет".to_string(), 0),
+            ("привед".to_string(), 1),
+            ("превед".to_string(), 2)
+        ],
+        m.suggest("привет", 2, 10)
+    );
+}
+
+#[test]
+fn for_each_ref() {
+    let m = prepare_data();
+    let mut entries = vec![];
+    m.for_each_ref(|key, value| entries.push((key.to_string(), value)));
+
+    assert_eq!(m.iter().collect::<Vec<_>>(), entries);
+    TSTMap::<i32>::new().for_each_ref(|_, _| unreachable!());
+}
+
+#[test]
+fn for_each() {
+    let m = prepare_data();
+    let mut keys = String::new();
+    let mut sum = 0;
+    m.for_each(|key, value| {
+        keys.push_str(key);
+        sum += value;
+    });
+
+    assert_eq!(m.keys().collect::<String>(), keys);
+    assert_eq!(91, sum);
+    TSTMap::<i32>::new().for_each(|_, _| unreachable!());
+}
+
+#[test]
+fn visit() {
+    let m = prepare_data();
+
+    let mut keys = vec![];
+    m.visit(|key, value, depth| {
+        assert_eq!(Some(value), m.get(key));
+        assert!(depth >= key.chars().count());
+        keys.push(key.to_string());
+    });
+    assert_eq!(m.keys().collect::<Vec<_>>(), keys);
+}
+
+#[test]
+fn visit_depth() {
+    let m = tstmap! {
+        "b" => 1,
+        "a" => 2,
+        "c" => 3,
+        "cd" => 4,
+    };
+
+    let mut visited = vec![];
+    m.visit(|key, _, depth| visited.push((key.to_string(), depth)));
+    assert_eq!(
+        vec![
+            ("a".to_string(), 2),
+            ("b".to_string(), 1),
+            ("c".to_string(), 2),
+            ("cd".to_string(), 3),
+        ],
+        visited
+    );
+}
+
+#[test]
+fn visit_unicode() {
+    let m = tstmap! {
+        "сухонос" => 1,
+        "ухонос" => 2,
+        "суx" => 3,
+    };
+
+    let mut keys = vec![];
+    m.visit(|key, _, _| keys.push(key.to_string()));
+    assert_eq!(m.keys().collect::<Vec<_>>(), keys);
+}
+
+#[test]
+fn with_max_entries() {
+    let mut m = TSTMap::with_max_entries(3);
+    for (i, key) in ["BYE", "BYTE", "BYWAY"].iter().enumerate() {
+        assert_eq!((None, None), m.insert_evicting(key, i));
+    }
+    // updates don't evict
+    assert_eq!((Some(1), None), m.insert_evicting("BYTE", 10));
+    assert_eq!(3, m.len());
+
+    assert_eq!(
+        (None, Some(("BYE".to_string(), 0))),
+        m.insert_evicting("BYWORD", 3)
+    );
+    assert_eq!(None, m.insert("BYZ", 4));
+    assert_eq!(3, m.len());
+    assert!(!m.contains_key("BYTE"));
+    assert_eq!(Ok(()), m.validate());
+
+    // every way of adding a key evicts
+    m.entry("A").or_insert(5);
+    m.entry_owned("B".to_string()).or_insert(6);
+    m.get_or_insert_with("C", || 7);
+    m.insert_reporting_depth("D", 8);
+    m.increment("E");
+    m.bump_by("F", 9);
+    m.insert_mut("G", 10);
+    m.merge_with(tstmap! { "H" => 11, "I" => 12 }, |_, _| {});
+    let mut cursor = m.cursor("J");
+    cursor.insert_suffix("K", 13);
+    cursor.insert_suffix("L", 14);
+    assert_eq!(vec!["I", "JK", "JL"], m.keys().collect::<Vec<_>>());
+    assert_eq!(Ok(()), m.validate());
+
+    m.clear();
+    m.extend(vec![("x", 1), ("y", 2), ("z", 3), ("w", 4)]);
+    assert_eq!(3, m.len());
+
+    let mut cpy = m.clone();
+    cpy.insert("v", 5);
+    assert_eq!(3, cpy.len());
+}
+
+#[cfg(not(feature = "insertion-order"))]
+#[test]
+fn with_max_entries_evicts_smallest() {
+    let mut m = TSTMap::with_max_entries(2);
+    m.insert("b", 1);
+    m.insert("a", 2);
+    assert_eq!(
+        (None, Some(("a".to_string(), 2))),
+        m.insert_evicting("c", 3)
+    );
+    // the new key is kept even if it's the smallest
+    assert_eq!(
+        (None, Some(("b".to_string(), 1))),
+        m.insert_evicting("0", 4)
+    );
+    assert_eq!(vec!["0", "c"], m.keys().collect::<Vec<_>>());
+}
+
+#[cfg(feature = "insertion-order")]
+#[test]
+fn with_max_entries_evicts_oldest() {
+    let mut m = TSTMap::with_max_entries(2);
+    m.insert("b", 1);
+    m.insert("a", 2);
+    assert_eq!(
+        (None, Some(("b".to_string(), 1))),
+        m.insert_evicting("c", 3)
+    );
+    assert_eq!(
+        (None, Some(("a".to_string(), 2))),
+        m.insert_evicting("0", 4)
+    );
+    assert_eq!(vec!["0", "c"], m.keys().collect::<Vec<_>>());
+
+    // removed keys and earlier inserts of keys inserted again are skipped
+    m.remove("c");
+    m.insert("c", 5);
+    m.insert("d", 6);
+    assert_eq!(vec!["c", "d"], m.keys().collect::<Vec<_>>());
+    m.remove("d");
+    for i in 0..100 {
+        m.insert(i.to_string(), i);
+        m.remove(i.to_string());
+    }
+    m.insert("e", 7);
+    assert_eq!(
+        (None, Some(("c".to_string(), 5))),
+        m.insert_evicting("f", 8)
+    );
+}
+
+#[test]
+fn cursor_with_max_entries() {
+    let mut m = TSTMap::with_max_entries(1);
+    let mut cursor = m.cursor("ab");
+    cursor.insert_suffix("c", 1);
+    // the nodes of the prefix go away with the only entry evicted
+    cursor.insert_suffix("d", 2);
+    assert_eq!(Some(&2), cursor.get_suffix("d"));
+    cursor.insert_suffix("", 3);
+    assert_eq!(vec![("ab".to_string(), &3)], m.iter().collect::<Vec<_>>());
+    assert_eq!(Ok(()), m.validate());
+}
+
+#[cfg(feature = "insertion-order")]
+#[test]
+fn iter_insertion_order() {
+    let mut m = TSTMap::new();
+    m.insert("BYTE", 1);
+    m.insert("BY", 2);
+    *m.entry("BYWAY").or_insert(0) += 3;
+    m.insert("ABC", 4);
+    m.insert("BY", 5);
+    m.merge_with(tstmap! { "AB" => 6, "BYTE" => 7 }, |old, new| *old += new);
+
+    assert_eq!(
+        vec![
+            ("BYTE".to_string(), &8),
+            ("BY".to_string(), &5),
+            ("BYWAY".to_string(), &3),
+            ("ABC".to_string(), &4),
+            ("AB".to_string(), &6),
+        ],
+        m.iter_insertion_order().collect::<Vec<_>>()
+    );
+
+    m.remove("BYTE");
+    m.insert("BYTE", 9);
+    let keys: Vec<String> = m.iter_insertion_order().map(|(k, _)| k).collect();
+    assert_eq!(vec!["BY", "BYWAY", "ABC", "AB", "BYTE"], keys);
+
+    let cpy = m.clone();
+    let keys: Vec<String> = cpy.iter_insertion_order().map(|(k, _)| k).collect();
+    assert_eq!(vec!["BY", "BYWAY", "ABC", "AB", "BYTE"], keys);
+    assert_eq!(0, TSTMap::<i32>::new().iter_insertion_order().count());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_iter() {
+    use rayon::prelude::*;
+
+    let m = prepare_data();
+    let vec: Vec<(String, &i32)> = m.par_iter().collect();
+    assert_eq!(m.iter().collect::<Vec<_>>(), vec);
+    assert_eq!(91, (&m).into_par_iter().map(|(_, v)| v).sum::<i32>());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_iter_empty() {
+    use rayon::prelude::*;
 
-    for (_, v) in m.iter_mut() {
-        *v *= 3;
-    }
-    assert_eq!(Some(&6), m.get("b"));
-    assert_eq!(Some(&3), m.get("a"));
-    assert_eq!(Some(&12), m.get("c"));
-    assert_eq!(Some(&39), m.get("aa"));
+    let m = TSTMap::<i32>::new();
+    assert_eq!(0, m.par_iter().count());
 }
 
 #[test]
@@ -462,6 +2786,59 @@ fn into_iter() {
     assert_eq!(orig, vec);
 }
 
+#[test]
+fn sorted_vec() {
+    let m = prepare_data();
+    let sorted = m.to_sorted_vec();
+    assert_eq!(m.len(), sorted.len());
+    assert_eq!(m.iter().collect::<Vec<_>>(), sorted);
+
+    let owned: Vec<_> = sorted.into_iter().map(|(k, v)| (k, *v)).collect();
+    assert_eq!(owned, m.into_sorted_vec());
+    assert!(TSTMap::<i32>::new().into_sorted_vec().is_empty());
+}
+
+#[test]
+fn extend_lossy() {
+    let mut m = prepare_data();
+    let rows = vec![
+        ("BYTE".to_string(), 20),
+        (String::new(), 21),
+        ("BYTES".to_string(), 22),
+        (String::new(), 23),
+    ];
+
+    assert_eq!(2, m.extend_lossy(rows));
+    assert_eq!(14, m.len());
+    assert_eq!(Some(&20), m.get("BYTE"));
+    assert_eq!(Some(&22), m.get("BYTES"));
+    assert_eq!(0, m.extend_lossy(vec![("BY", 1)]));
+    assert_eq!(1, TSTMap::new().extend_lossy(vec![("", 1)]));
+    assert_eq!(Ok(()), m.validate());
+}
+
+#[test]
+fn try_from_iter_unique() {
+    let vec = vec![("b", 2), ("a", 1), ("ab", 3)];
+    let m = TSTMap::try_from_iter_unique(vec.clone()).unwrap();
+    assert_eq!(TSTMap::from_iter(vec), m);
+
+    let dup = vec![
+        ("b".to_string(), 2),
+        ("a".to_string(), 1),
+        ("b".to_string(), 4),
+    ];
+    match TSTMap::try_from_iter_unique(dup) {
+        Err((key, value)) => assert_eq!(("b".to_string(), 4), (key, value)),
+        Ok(_) => unreachable!(),
+    }
+    assert!(
+        TSTMap::<i32>::try_from_iter_unique(Vec::<(&str, i32)>::new())
+            .unwrap()
+            .is_empty()
+    );
+}
+
 #[test]
 fn from_iterator_empty() {
     let vec = vec![];
@@ -500,6 +2877,108 @@ fn extend() {
     assert_eq!(orig, m);
 }
 
+#[test]
+fn merge_with() {
+    let mut m = tstmap! {
+        "a" => 1,
+        "ab" => 2,
+        "b" => 3,
+    };
+    let other = tstmap! {
+        "ab" => 10,
+        "abc" => 20,
+        "b" => 30,
+        "c" => 40,
+    };
+    m.merge_with(other, |existing, incoming| *existing += incoming);
+    let orig = tstmap! {
+        "a" => 1,
+        "ab" => 12,
+        "abc" => 20,
+        "b" => 33,
+        "c" => 40,
+    };
+    assert_eq!(orig, m);
+    assert_eq!(5, m.len());
+}
+
+#[test]
+fn merge_with_keep_existing() {
+    let mut m = prepare_data();
+    let other = prepare_data();
+    m.merge_with(other, |_, _| {});
+    assert_eq!(prepare_data(), m);
+    assert_eq!(13, m.len());
+
+    m.merge_with(TSTMap::new(), |_, _| unreachable!());
+    assert_eq!(13, m.len());
+}
+
+#[test]
+fn merge_sorted_maps() {
+    let shards = vec![
+        tstmap! { "a" => 1, "ab" => 2, "b" => 3 },
+        TSTMap::new(),
+        tstmap! { "ab" => 10, "abc" => 20, "b" => 30 },
+        tstmap! { "b" => 300, "c" => 400 },
+    ];
+    let mut order = vec![];
+    let m = merge_sorted(shards, |existing, incoming| {
+        order.push(incoming);
+        *existing += incoming;
+    });
+    let expected = tstmap! {
+        "a" => 1,
+        "ab" => 12,
+        "abc" => 20,
+        "b" => 333,
+        "c" => 400,
+    };
+    assert_eq!(expected, m);
+    assert_eq!(5, m.len());
+    assert_eq!(vec![10, 30, 300], order);
+    assert_eq!(Ok(()), m.validate());
+
+    let entries = prepare_data().into_sorted_vec();
+    let parts: Vec<TSTMap<i32>> = (0..4)
+        .map(|i| {
+            let mut part = TSTMap::new();
+            part.extend(
+                entries
+                    .iter()
+                    .skip(i)
+                    .step_by(4)
+                    .map(|(k, v)| (k.as_str(), *v)),
+            );
+            part
+        })
+        .collect();
+    let m = merge_sorted(parts, |_, _| unreachable!());
+    assert_eq!(prepare_data(), m);
+    assert_eq!(13, m.len());
+    assert_eq!(Ok(()), m.validate());
+
+    assert!(merge_sorted(Vec::<TSTMap<i32>>::new(), |_, _| {}).is_empty());
+}
+
+#[test]
+fn merge_sorted_settings() {
+    let mut a = TSTMap::with_max_entries(2);
+    a.insert("a", 1);
+    a.insert("c", 3);
+    let b = tstmap! { "b" => 2, "d" => 4 };
+    let m = merge_sorted(vec![a, b], |_, _| unreachable!());
+    // entries beyond the limit are evicted as they are inserted
+    assert_eq!(vec!["c", "d"], m.keys().collect::<Vec<_>>());
+
+    let mut a = TSTMap::with_bloom(10);
+    a.insert("a", 1);
+    let m = merge_sorted(vec![a, tstmap! { "b" => 2 }], |_, _| unreachable!());
+    assert_eq!(Some(&1), m.get("a"));
+    assert_eq!(Some(&2), m.get("b"));
+    assert_eq!(None, m.get("c"));
+}
+
 #[test]
 fn prefix_iterator_empty() {
     let mut m = TSTMap::new();
@@ -535,6 +3014,26 @@ fn prefix_iterator() {
     );
 }
 
+#[test]
+fn prefix_iterator_size_hint() {
+    let mut m = prepare_data();
+
+    let mut iter = m.prefix_iter("BYP");
+    for left in (0..3).rev() {
+        assert!(iter.next().is_some());
+        assert_eq!((left, Some(left)), iter.size_hint());
+    }
+    assert_eq!(None, iter.next());
+    assert_eq!((0, Some(0)), iter.size_hint());
+
+    assert_eq!((13, Some(13)), m.prefix_iter("BY").size_hint());
+    assert_eq!((1, Some(1)), m.prefix_iter("BYE").size_hint());
+    assert_eq!((1, Some(1)), m.prefix_iter("BYPASS").size_hint());
+    assert_eq!((0, Some(0)), m.prefix_iter("BYX").size_hint());
+    assert_eq!(2, m.prefix_iter_mut("BYW").len());
+    assert_eq!(0, m.prefix_iter_mut("BYTES").len());
+}
+
 #[test]
 fn prefix_iterator_only_one() {
     let m = prepare_data();
@@ -546,6 +3045,25 @@ fn prefix_iterator_only_one() {
     assert_eq!("(\"BYE\", 2)", m_str);
 }
 
+#[test]
+fn prefix_iterator_long_prefix() {
+    let mut m = TSTMap::new();
+    let prefix = "/srv/data/".repeat(100);
+    for (i, name) in ["b", "a", "c/d", "c"].iter().enumerate() {
+        m.insert(format!("{}{}", prefix, name), i);
+    }
+    m.insert("/srv", 10);
+
+    let expected: Vec<String> = ["a", "b", "c", "c/d"]
+        .iter()
+        .map(|name| format!("{}{}", prefix, name))
+        .collect();
+    assert_eq!(expected, m.prefix_keys(&prefix).collect::<Vec<_>>());
+    let mut rev: Vec<String> = m.prefix_keys(&prefix[..5]).rev().collect();
+    rev.reverse();
+    assert_eq!(expected, rev);
+}
+
 #[test]
 fn prefix_iterator_mut() {
     let mut m = TSTMap::new();
@@ -579,6 +3097,43 @@ fn prefix_iterator_mut_empty() {
     assert_eq!(orig, m);
 }
 
+#[test]
+fn path_iter() {
+    let mut m = TSTMap::new();
+    for (i, path) in ["/var/log", "/etc/hosts", "/var/lib/данные"]
+        .iter()
+        .enumerate()
+    {
+        assert_eq!(None, m.insert_path(Path::new(path), i));
+    }
+    assert_eq!(
+        Some(1),
+        m.insert_path(&PathBuf::from("/etc").join("hosts"), 10)
+    );
+
+    let paths: Vec<(PathBuf, &usize)> = m.path_iter().collect();
+    assert_eq!(
+        vec![
+            (PathBuf::from("/etc/hosts"), &10),
+            (PathBuf::from("/var/lib/данные"), &2),
+            (PathBuf::from("/var/log"), &0),
+        ],
+        paths
+    );
+    assert_eq!(3, m.path_iter().len());
+}
+
+#[cfg(unix)]
+#[test]
+fn insert_path_non_unicode() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut m = TSTMap::new();
+    m.insert_path(Path::new(OsStr::from_bytes(b"/tmp/a\xffb")), 1);
+    assert_eq!(Some(&1), m.get("/tmp/a\u{fffd}b"));
+}
+
 #[test]
 fn keys_iterator() {
     let mut m = TSTMap::new();
@@ -595,6 +3150,202 @@ fn keys_iterator() {
     assert_eq!("\"abc\"\"abcd\"\"bcd\"\"c\"", m_str);
 }
 
+#[test]
+fn keys_iterator_double_ended() {
+    let m = prepare_data();
+    let mut keys: Vec<String> = m.keys().collect();
+    assert_eq!(m.len(), m.keys().len());
+
+    keys.reverse();
+    assert_eq!(keys, m.keys().rev().collect::<Vec<_>>());
+
+    // both ends meet in the middle without yielding a key twice
+    let mut iter = m.keys();
+    let mut front = vec![];
+    let mut back = vec![];
+    while let Some(key) = iter.next() {
+        front.push(key);
+        assert_eq!(m.len() - front.len() - back.len(), iter.len());
+        if let Some(key) = iter.next_back() {
+            back.push(key);
+        }
+    }
+    assert_eq!(None, iter.next_back());
+    back.reverse();
+    front.extend(back);
+    keys.reverse();
+    assert_eq!(keys, front);
+
+    assert_eq!(
+        vec!["BYPRODUCT", "BYPATH", "BYPASS"],
+        m.prefix_keys("BYP").rev().collect::<Vec<_>>()
+    );
+    assert_eq!(3, m.prefix_iter("BYP").len());
+    assert_eq!(0, TSTMap::<i32>::new().keys().rev().count());
+}
+
+#[test]
+fn walk() {
+    let m = prepare_data();
+
+    let mut keys = vec![];
+    m.walk(|key, _| {
+        keys.push(key.to_string());
+        ControlFlow::Continue(())
+    });
+    assert_eq!(m.keys().collect::<Vec<_>>(), keys);
+
+    let mut visited = 0;
+    m.walk(|key, value| {
+        visited += 1;
+        if key > "BYL" {
+            return ControlFlow::Break(());
+        }
+        assert!(*value < 5);
+        ControlFlow::Continue(())
+    });
+    assert_eq!(4, visited);
+
+    TSTMap::<i32>::new().walk(|_, _| unreachable!());
+}
+
+#[test]
+fn completions() {
+    let m = prepare_data();
+
+    let mut completions: Vec<(String, &i32)> = m.completions("BYP").collect();
+    completions.sort();
+    assert_eq!(
+        vec![
+            ("ASS".to_string(), &6),
+            ("ATH".to_string(), &7),
+            ("RODUCT".to_string(), &8)
+        ],
+        completions
+    );
+    let mut completions: Vec<(String, &i32)> = m.completions("BY").collect();
+    completions.sort();
+    assert_eq!(13, completions.len());
+    assert_eq!(("".to_string(), &1), completions[0]);
+    assert_eq!(0, m.completions("BYX").count());
+}
+
+#[test]
+fn group_by_first_char() {
+    let mut m = tstmap! {
+        "mango" => 1,
+        "apple" => 2,
+        "кот" => 3,
+        "zebra" => 4,
+        "avocado" => 5,
+        "m" => 6,
+        "dates" => 7,
+    };
+    m.remove("dates");
+
+    let groups: Vec<(char, Vec<String>)> = m
+        .group_by_first_char()
+        .map(|(c, it)| {
+            let mut keys: Vec<_> = it.map(|(k, _)| k).collect();
+            keys.sort();
+            (c, keys)
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            ('a', vec!["apple".to_string(), "avocado".to_string()]),
+            ('m', vec!["m".to_string(), "mango".to_string()]),
+            ('z', vec!["zebra".to_string()]),
+            ('к', vec!["кот".to_string()]),
+        ],
+        groups
+    );
+    assert_eq!(0, TSTMap::<i32>::new().group_by_first_char().count());
+}
+
+#[test]
+fn shard_by_first_char() {
+    let mut m = tstmap! {
+        "mango" => 1,
+        "apple" => 2,
+        "кот" => 3,
+        "zebra" => 4,
+        "avocado" => 5,
+        "m" => 6,
+        "dates" => 7,
+    };
+    m.remove("dates");
+    let orig = m.clone();
+
+    let shards = m.shard_by_first_char();
+    assert_eq!(
+        vec!['a', 'm', 'z', 'к'],
+        shards.iter().map(|(c, _)| *c).collect::<Vec<_>>()
+    );
+    for (c, shard) in &shards {
+        assert_eq!(Ok(()), shard.validate());
+        assert_eq!(orig.prefix_iter(&c.to_string()).count(), shard.len());
+        for (key, value) in shard.iter() {
+            assert_eq!(Some(value), orig.get(&key));
+        }
+    }
+
+    let (_, mut shard) = shards.into_iter().nth(1).unwrap();
+    shard.insert("melon", 8);
+    assert_eq!(
+        vec!["m", "mango", "melon"],
+        shard.keys().collect::<Vec<_>>()
+    );
+    assert!(TSTMap::<i32>::new().shard_by_first_char().is_empty());
+}
+
+#[test]
+fn completions_unicode() {
+    let m = tstmap! {
+        "при" => 1,
+        "привет" => 2,
+    };
+
+    assert_eq!(
+        vec!["".to_string(), "вет".to_string()],
+        m.completions("при").map(|(s, _)| s).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn prefix_keys_iterator() {
+    let m = prepare_data();
+
+    let keys: Vec<String> = m.prefix_keys("BYP").collect();
+    assert_eq!(vec!["BYPASS", "BYPATH", "BYPRODUCT"], keys);
+    assert_eq!(0, m.prefix_keys("BYX").count());
+    assert_eq!(vec!["BYE"], m.prefix_keys("BYE").collect::<Vec<_>>());
+}
+
+#[test]
+fn prefix_keys_with_prefix_key() {
+    let m = tstmap! {
+        "abc" => 2,
+        "ab" => 1,
+        "abd" => 3,
+        "b" => 4,
+    };
+
+    assert_eq!(
+        vec!["ab", "abc", "abd"],
+        m.prefix_keys("ab").collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["abd", "abc", "ab"],
+        m.prefix_keys("ab").rev().collect::<Vec<_>>()
+    );
+    let mut iter = m.prefix_iter("ab");
+    assert_eq!(Some(("ab".to_string(), &1)), iter.next());
+    assert_eq!(Some(("abd".to_string(), &3)), iter.next_back());
+    assert_eq!(Some(("abc".to_string(), &2)), iter.next());
+    assert_eq!(None, iter.next_back());
+}
+
 #[test]
 fn values_iterator() {
     let m = tstmap! {
@@ -613,6 +3364,83 @@ fn values_iterator() {
     assert_eq!("1 13 2 3 130 ", m_str);
 }
 
+#[test]
+fn values_iterator_double_ended() {
+    let mut m = tstmap! {
+        "abc" => 1,
+        "bcd" => 2,
+        "c" => 3,
+        "abcd" => 13,
+        "xxx" => 130,
+    };
+    m.remove("bcd");
+
+    assert_eq!(4, m.values().len());
+    assert_eq!(
+        vec![&130, &3, &13, &1],
+        m.values().rev().collect::<Vec<_>>()
+    );
+
+    let mut iter = m.values();
+    assert_eq!(Some(&1), iter.next());
+    assert_eq!(Some(&130), iter.next_back());
+    assert_eq!(2, iter.len());
+    assert_eq!(Some(&3), iter.next_back());
+    assert_eq!(Some(&13), iter.next());
+    assert_eq!(None, iter.next());
+    assert_eq!(None, iter.next_back());
+}
+
+#[test]
+fn entries_sorted_by_value() {
+    let m = tstmap! {
+        "d" => 2,
+        "a" => 3,
+        "c" => 1,
+        "b" => 2,
+    };
+
+    let sorted: Vec<(String, i32)> = m
+        .entries_sorted_by_value()
+        .into_iter()
+        .map(|(k, v)| (k, *v))
+        .collect();
+    let orig = vec![
+        ("c".to_string(), 1),
+        ("b".to_string(), 2),
+        ("d".to_string(), 2),
+        ("a".to_string(), 3),
+    ];
+    assert_eq!(orig, sorted);
+    assert!(TSTMap::<i32>::new().entries_sorted_by_value().is_empty());
+}
+
+#[test]
+fn entries_sorted_by() {
+    let m = prepare_data();
+
+    let sorted = m.entries_sorted_by(|a, b| (b % 3).cmp(&(a % 3)));
+    let keys: Vec<&str> = sorted.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(
+        vec![
+            "BYE",
+            "BYLINE",
+            "BYPRODUCT",
+            "BYTE",
+            "BY",
+            "BYLAW",
+            "BYPATH",
+            "BYSTANDER",
+            "BYWORD",
+            "BYGONE",
+            "BYPASS",
+            "BYROAD",
+            "BYWAY"
+        ],
+        keys
+    );
+}
+
 #[test]
 fn wildcard_iter_simple() {
     let m = tstmap! {
@@ -686,6 +3514,77 @@ fn wildcard_iter_empty() {
     assert_eq!("", m_str);
 }
 
+#[test]
+fn wildcard_iter_size_hint() {
+    let mut m = prepare_data();
+
+    assert_eq!((0, Some(13)), m.wildcard_iter(".Y....").size_hint());
+    assert_eq!((0, Some(12)), m.wildcard_iter("BY.").size_hint());
+    assert_eq!((0, Some(3)), m.wildcard_iter("BYP.....").size_hint());
+    assert_eq!((0, Some(1)), m.wildcard_iter("BYPRODUCT").size_hint());
+    assert_eq!((0, Some(0)), m.wildcard_iter("BYPRODUCT.").size_hint());
+    assert_eq!((0, Some(0)), m.wildcard_iter("BYX..").size_hint());
+    assert_eq!((0, Some(0)), m.wildcard_iter("").size_hint());
+    assert_eq!((0, Some(2)), m.wildcard_iter_mut("BYW...").size_hint());
+
+    let mut iter = m.wildcard_iter("BYP...");
+    assert_eq!(Some(("BYPASS".to_string(), &6)), iter.next());
+    assert_eq!((0, Some(2)), iter.size_hint());
+    assert_eq!(Some(("BYPATH".to_string(), &7)), iter.next());
+    assert_eq!(None, iter.next());
+}
+
+// naive matcher of `.` and `*` patterns
+fn glob_matches(pat: &[char], key: &[char]) -> bool {
+    match pat.split_first() {
+        None => key.is_empty(),
+        Some(('*', rest)) => (0..=key.len()).any(|i| glob_matches(rest, &key[i..])),
+        Some((&c, rest)) => {
+            !key.is_empty() && (c == '.' || c == key[0]) && glob_matches(rest, &key[1..])
+        }
+    }
+}
+
+#[test]
+fn matching() {
+    let m = prepare_data();
+
+    let keys = |pat: &str| m.matching(pat).keys().collect::<Vec<_>>();
+    assert_eq!(vec!["BYPASS", "BYPATH", "BYPRODUCT"], keys("BYP*"));
+    assert_eq!(
+        vec!["BYLAW", "BYPASS", "BYPATH", "BYROAD", "BYSTANDER", "BYWAY"],
+        keys("*A*")
+    );
+    assert_eq!(vec!["BYE", "BYGONE", "BYLINE", "BYTE"], keys("*E"));
+    assert_eq!(vec!["BYTE"], keys("BYTE*"));
+    assert_eq!(vec!["BYTE"], keys("BYTE"));
+    assert!(keys("BYTE.*").is_empty());
+    assert!(keys("").is_empty());
+    assert_eq!(13, keys("*").len());
+    assert_eq!(13, keys("**").len());
+    assert_eq!(Some(&6), m.matching("*SS").get("BYPASS"));
+
+    for pat in [
+        "*", "B*Y", "*Y*", "BY*E*", "*.A*", "B..*.", "*O*D", "**T*", "*.*.*.*", ".*R.*",
+    ] {
+        let pat_chars: Vec<char> = pat.chars().collect();
+        let expected: Vec<_> = m
+            .keys()
+            .filter(|k| glob_matches(&pat_chars, &k.chars().collect::<Vec<_>>()))
+            .collect();
+        let found = m.matching(pat);
+        assert_eq!(expected, found.keys().collect::<Vec<_>>(), "{}", pat);
+        assert_eq!(Ok(()), found.validate());
+    }
+
+    // `*` is an ordinary char for wildcard iterators
+    let mut m = TSTMap::new();
+    m.insert("a*", 1);
+    m.insert("ab", 2);
+    assert_eq!(1, m.wildcard_iter("a*").count());
+    assert_eq!(2, m.matching("a*").len());
+}
+
 #[test]
 fn wildcard_iter_mut() {
     let mut m = prepare_data();
@@ -699,6 +3598,52 @@ fn wildcard_iter_mut() {
     assert_eq!(5, m["BYLINE"]);
 }
 
+#[test]
+fn wildcard_iter_mut_all_at_once() {
+    let mut m = prepare_data();
+
+    let mut values: Vec<_> = m.wildcard_iter_mut("BY...").map(|(_, v)| v).collect();
+    assert_eq!(2, values.len());
+    for v in values.iter_mut() {
+        **v *= 10;
+    }
+    assert_eq!(40, m["BYLAW"]);
+    assert_eq!(120, m["BYWAY"]);
+    assert_eq!(11, m["BYTE"]);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regex_iter() {
+    use regex::Regex;
+
+    let m = prepare_data();
+
+    let re = Regex::new("BY[A-Z]{4}").unwrap();
+    let keys: Vec<_> = m.regex_iter(&re).map(|(k, _)| k).collect();
+    assert_eq!(
+        vec!["BYGONE", "BYLINE", "BYPASS", "BYPATH", "BYROAD", "BYWORD"],
+        keys
+    );
+
+    let re = Regex::new("BY|BYE").unwrap();
+    assert_eq!(3, m.regex_iter(&re).map(|(_, v)| v).sum::<i32>());
+
+    let re = Regex::new("(?i)byte").unwrap();
+    assert_eq!(
+        vec![("BYTE".to_string(), &11)],
+        m.regex_iter(&re).collect::<Vec<_>>()
+    );
+
+    let re = Regex::new("Y").unwrap();
+    assert_eq!(0, m.regex_iter(&re).count());
+
+    // a comment of the verbose mode running to the end of the pattern
+    let re = Regex::new("(?x) BY [A-Z]{2} # a short key").unwrap();
+    let keys: Vec<_> = m.regex_iter(&re).map(|(k, _)| k).collect();
+    assert_eq!(vec!["BYTE"], keys);
+}
+
 #[test]
 fn wildcard_iter_unicode() {
     let mut m = TSTMap::new();
@@ -715,6 +3660,42 @@ fn wildcard_iter_unicode() {
     assert_eq!("(\"сухонос\", 1000)", m_str);
 }
 
+#[test]
+fn wildcard_iter_class() {
+    let m = tstmap! {
+        "gray" => 1,
+        "grey" => 2,
+        "gryy" => 3,
+        "groy" => 4,
+    };
+
+    let keys: Vec<String> = m.wildcard_iter("gr[ae]y").map(|(k, _)| k).collect();
+    assert_eq!(vec!["gray", "grey"], keys);
+    let keys: Vec<String> = m.wildcard_iter("gr[yo].").map(|(k, _)| k).collect();
+    assert_eq!(vec!["groy", "gryy"], keys);
+    let keys: Vec<String> = m.wildcard_iter("g.[^ae]y").map(|(k, _)| k).collect();
+    assert_eq!(vec!["groy", "gryy"], keys);
+    assert_eq!(0, m.wildcard_iter("gr[]y").count());
+}
+
+#[test]
+fn wildcard_iter_unclosed_bracket() {
+    let m = tstmap! {
+        "a[b" => 1,
+        "acb" => 2,
+    };
+
+    let keys: Vec<String> = m.wildcard_iter("a[b").map(|(k, _)| k).collect();
+    assert_eq!(vec!["a[b"], keys);
+}
+
+#[test]
+fn wildcard_iter_empty_pattern() {
+    let m = prepare_data();
+
+    assert_eq!(0, m.wildcard_iter("").count());
+}
+
 #[test]
 fn eq_empty() {
     let m1 = TSTMap::<i32>::new();
@@ -819,3 +3800,83 @@ fn unicode() {
     assert_eq!(Some(12), m.remove("::ХУЙ"));
     assert_eq!(None, m.get("::ХУЙ"));
 }
+
+fn reversed_chars(a: char, b: char) -> Ordering {
+    b.cmp(&a)
+}
+
+#[test]
+fn with_comparator() {
+    let mut m = TSTMap::with_comparator(reversed_chars);
+    for (key, value) in prepare_data().iter() {
+        m.insert(&key, *value);
+    }
+    assert_eq!(Ok(()), m.validate());
+    assert_eq!(13, m.len());
+    assert_eq!(Some(&2), m.get("BYE"));
+    assert_eq!(None, m.get("BYX"));
+    assert_eq!("BYLAW", m.longest_prefix("BYLAWS"));
+
+    let mut keys: Vec<_> = prepare_data().keys().collect();
+    keys.sort_by(|a, b| a.chars().map(Reverse).cmp(b.chars().map(Reverse)));
+    assert_eq!(keys, m.keys().collect::<Vec<_>>());
+    assert_eq!(
+        vec!["BYPRODUCT", "BYPATH", "BYPASS"],
+        m.prefix_iter("BYP").map(|(k, _)| k).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["BYPATH", "BYPASS", "BYLINE"],
+        m.iter_from("BYPB")
+            .take(3)
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>()
+    );
+    let mut iter = m.iter();
+    iter.advance_to("BYL");
+    assert_eq!(Some("BYLINE".to_string()), iter.next().map(|(k, _)| k));
+    for (i, key) in keys.iter().enumerate() {
+        assert_eq!(i, m.rank(key));
+        assert_eq!(Some(key), m.nth(i).map(|(k, _)| k).as_ref());
+    }
+    assert_eq!(
+        vec!["BYWAY", "BYLAW"],
+        m.wildcard_iter("BY.A.").map(|(k, _)| k).collect::<Vec<_>>()
+    );
+
+    assert_eq!(Some(8), m.remove("BYPRODUCT"));
+    m.entry("BYPASSED").or_insert(14);
+    m.canonicalize();
+    assert_eq!(Ok(()), m.validate());
+    assert_eq!(Some("BY".to_string()), m.peek_min().map(|(k, _)| k));
+    assert_eq!(Some("BYE".to_string()), m.peek_max().map(|(k, _)| k));
+
+    let copy = m.clone();
+    assert_eq!(Ok(()), copy.validate());
+    assert_eq!(m, copy);
+
+    // binary formats store the keys in code point order
+    let mut blob = vec![];
+    m.write_to(&mut blob).unwrap();
+    let loaded: TSTMap<i32> = TSTMap::read_from(&mut &blob[..]).unwrap();
+    assert_eq!(Ok(()), loaded.validate());
+    assert_eq!(Some("BY".to_string()), loaded.keys().next());
+    assert_eq!(m.len(), loaded.len());
+    for (key, value) in m.iter() {
+        assert_eq!(Some(value), loaded.get(&key));
+    }
+
+    let merged = merge_sorted(vec![m.clone(), copy], |existing, incoming| {
+        *existing += incoming
+    });
+    assert_eq!(Ok(()), merged.validate());
+    assert_eq!(Some(&28), merged.get("BYPASSED"));
+    assert_eq!(
+        m.keys().collect::<Vec<_>>(),
+        merged.keys().collect::<Vec<_>>()
+    );
+
+    m.clear();
+    m.insert("b", 1);
+    m.insert("a", 2);
+    assert_eq!(vec!["b", "a"], m.keys().collect::<Vec<_>>());
+}